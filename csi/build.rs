@@ -1,6 +1,21 @@
 extern crate tonic_build;
 
+use chrono::{TimeZone, Utc};
+
+/// Date of the build, taken from SOURCE_DATE_EPOCH when it is set so that
+/// reproducible builds report the date of their sources.
+fn build_date() -> String {
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<i64>().ok())
+        .map(|epoch| Utc.timestamp(epoch, 0))
+        .unwrap_or_else(Utc::now)
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
 fn main() {
+    println!("cargo:rustc-env=CSI_BUILD_DATE={}", build_date());
     tonic_build::configure()
         .build_server(true)
         .compile(&["proto/csi.proto"], &["proto"])
@@ -29,6 +29,8 @@ pub(super) struct NvmfAttach {
     uuid: Uuid,
     nqn: String,
     io_timeout: Option<u32>,
+    reconnect_delay: Option<u32>,
+    ctrl_loss_tmo: Option<u32>,
 }
 
 impl NvmfAttach {
@@ -39,6 +41,8 @@ impl NvmfAttach {
             uuid,
             nqn,
             io_timeout: None,
+            reconnect_delay: None,
+            ctrl_loss_tmo: None,
         }
     }
 
@@ -94,12 +98,24 @@ impl TryFrom<&Url> for NvmfAttach {
 
         let port = url.port().unwrap_or(4420);
 
-        Ok(NvmfAttach::new(
-            host.to_string(),
-            port,
-            uuid,
-            segments[0].to_string(),
-        ))
+        let mut attach =
+            NvmfAttach::new(host.to_string(), port, uuid, segments[0].into());
+
+        // reconnect tuning requested when the nexus was shared
+        for (key, value) in url.query_pairs() {
+            let parsed = || {
+                value.parse::<u32>().map_err(|_| {
+                    DeviceError::from(format!("invalid {}: \"{}\"", key, value))
+                })
+            };
+            match key.as_ref() {
+                "reconnect_delay" => attach.reconnect_delay = Some(parsed()?),
+                "ctrl_loss_tmo" => attach.ctrl_loss_tmo = Some(parsed()?),
+                _ => {}
+            }
+        }
+
+        Ok(attach)
     }
 }
 
@@ -123,6 +139,7 @@ impl Attach for NvmfAttach {
     async fn attach(&self) -> Result<(), DeviceError> {
         // The default reconnect delay in linux kernel is set to 10s. Use the
         // same default value unless the timeout is less or equal to 10.
+        // Values given with the share uri take precedence.
         let reconnect_delay = match self.io_timeout {
             Some(io_timeout) => {
                 if io_timeout <= 10 {
@@ -137,8 +154,8 @@ impl Attach for NvmfAttach {
             .traddr(&self.host)
             .trsvcid(self.port.to_string())
             .nqn(&self.nqn)
-            .ctrl_loss_tmo(self.io_timeout)
-            .reconnect_delay(reconnect_delay)
+            .ctrl_loss_tmo(self.ctrl_loss_tmo.or(self.io_timeout))
+            .reconnect_delay(self.reconnect_delay.or(reconnect_delay))
             .build()?;
         match ca.connect() {
             Err(NvmeError::ConnectInProgress) => Ok(()),
@@ -250,3 +267,50 @@ pub(crate) fn set_nvmecore_iotimeout(
     sysfs::write_value(path, "io_timeout", io_timeout_secs)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use url::Url;
+
+    use super::NvmfAttach;
+
+    const URI: &str = "nvmf://192.168.0.1:4421/nqn.2019-05.io.openebs:nexus-3c7e5e7e-8a4b-4e4a-9d0e-7e3f7f5f1a2b";
+
+    fn parse(query: &str) -> Result<NvmfAttach, String> {
+        let url = Url::parse(&format!("{}{}", URI, query)).unwrap();
+        NvmfAttach::try_from(&url).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn nvmf_uri_reconnect_tuning() {
+        let attach = parse("?reconnect_delay=5&ctrl_loss_tmo=60").unwrap();
+        assert_eq!(attach.host, "192.168.0.1");
+        assert_eq!(attach.port, 4421);
+        assert_eq!(attach.reconnect_delay, Some(5));
+        assert_eq!(attach.ctrl_loss_tmo, Some(60));
+
+        // unknown parameters are ignored
+        let attach = parse("?ctrl_loss_tmo=0&foo=bar").unwrap();
+        assert_eq!(attach.reconnect_delay, None);
+        assert_eq!(attach.ctrl_loss_tmo, Some(0));
+    }
+
+    #[test]
+    fn nvmf_uri_reconnect_tuning_missing() {
+        let attach = parse("").unwrap();
+        assert_eq!(attach.reconnect_delay, None);
+        assert_eq!(attach.ctrl_loss_tmo, None);
+    }
+
+    #[test]
+    fn nvmf_uri_reconnect_tuning_invalid() {
+        let error = parse("?reconnect_delay=soon").err().unwrap();
+        assert_eq!(error, "invalid reconnect_delay: \"soon\"");
+        assert!(parse("?reconnect_delay=-1").is_err());
+        assert!(parse("?reconnect_delay=").is_err());
+        assert!(parse("?ctrl_loss_tmo=1.5").is_err());
+        assert!(parse("?reconnect_delay=1&ctrl_loss_tmo=99999999999").is_err());
+    }
+}
@@ -1,12 +1,24 @@
 //! Implementation of gRPC methods from CSI Identity gRPC service.
 
 use super::csi::*;
+use git_version::git_version;
 use std::{boxed::Box, collections::HashMap};
 use tonic::{Request, Response, Status};
 
 const PLUGIN_NAME: &str = "io.openebs.csi-mayastor";
-// TODO: can we generate version with commit SHA dynamically?
-const PLUGIN_VERSION: &str = "0.2";
+/// major.minor version reported as the vendor version, which moac expects
+const PLUGIN_VERSION: &str = concat!(
+    env!("CARGO_PKG_VERSION_MAJOR"),
+    ".",
+    env!("CARGO_PKG_VERSION_MINOR")
+);
+/// full version of the plugin
+const PLUGIN_BUILD_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// git revision the plugin was built from
+const PLUGIN_GIT_VERSION: &str =
+    git_version!(args = ["--tags", "--abbrev=12"], fallback = "unknown");
+/// date the plugin was built on
+const PLUGIN_BUILD_DATE: &str = env!("CSI_BUILD_DATE");
 
 #[derive(Clone, Debug)]
 pub struct Identity {}
@@ -18,12 +30,26 @@ impl identity_server::Identity for Identity {
         &self,
         _request: Request<GetPluginInfoRequest>,
     ) -> Result<Response<GetPluginInfoResponse>, Status> {
-        debug!("GetPluginInfo request ({}:{})", PLUGIN_NAME, PLUGIN_VERSION);
+        debug!(
+            "GetPluginInfo request ({}:{} {} {})",
+            PLUGIN_NAME,
+            PLUGIN_BUILD_VERSION,
+            PLUGIN_GIT_VERSION,
+            PLUGIN_BUILD_DATE
+        );
+
+        let mut manifest = HashMap::new();
+        manifest
+            .insert("version".to_string(), PLUGIN_BUILD_VERSION.to_string());
+        manifest
+            .insert("git_version".to_string(), PLUGIN_GIT_VERSION.to_string());
+        manifest
+            .insert("build_date".to_string(), PLUGIN_BUILD_DATE.to_string());
 
         Ok(Response::new(GetPluginInfoResponse {
             name: PLUGIN_NAME.to_owned(),
             vendor_version: PLUGIN_VERSION.to_owned(),
-            manifest: HashMap::new(),
+            manifest,
         }))
     }
 
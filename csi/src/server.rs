@@ -13,6 +13,7 @@ use std::{
     fs,
     io::{ErrorKind, Write},
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 use crate::{identity::Identity, mount::probe_filesystems, node::Node};
@@ -169,6 +170,14 @@ async fn main() -> Result<(), String> {
                 .required(false)
                 .help("Sets the global nvme_core module io_timeout, in seconds"),
         )
+        .arg(
+            Arg::with_name("csi-socket-grace")
+                .long("csi-socket-grace")
+                .value_name("SECONDS")
+                .takes_value(true)
+                .required(false)
+                .help("Time to wait for a previous instance to release the CSI socket (default 10s)"),
+        )
         .get_matches();
 
     let node_name = normalize_hostname(matches.value_of("node-name").unwrap());
@@ -218,6 +227,18 @@ async fn main() -> Result<(), String> {
         }
     }
 
+    let grace = matches
+        .value_of("csi-socket-grace")
+        .map(|secs| {
+            secs.parse::<u64>()
+                .map_err(|_| format!("Invalid csi-socket-grace: {}", secs))
+        })
+        .transpose()?
+        .map_or(CSI_SOCKET_GRACE, Duration::from_secs);
+
+    // A previous instance may still be draining requests on the socket
+    wait_for_socket_release(csi_socket, grace).await?;
+
     // Remove stale CSI socket from previous instance if there is any
     match fs::remove_file(csi_socket) {
         Ok(_) => info!("Removed stale CSI socket {}", csi_socket),
@@ -247,6 +268,29 @@ async fn main() -> Result<(), String> {
     Ok(())
 }
 
+/// default time to wait for a live CSI socket to be released
+const CSI_SOCKET_GRACE: Duration = Duration::from_secs(10);
+
+/// Waits until no process accepts connections on the CSI socket anymore, so
+/// that it can be safely removed. Gives up once the grace period has expired.
+async fn wait_for_socket_release(
+    csi_socket: &str,
+    grace: Duration,
+) -> Result<(), String> {
+    let deadline = Instant::now() + grace;
+    while tokio::net::UnixStream::connect(csi_socket).await.is_ok() {
+        if Instant::now() >= deadline {
+            return Err(format!(
+                "CSI socket {} is still in use after {:?}",
+                csi_socket, grace
+            ));
+        }
+        info!("CSI socket {} is in use, waiting for release", csi_socket);
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    Ok(())
+}
+
 struct CsiServer {}
 
 impl CsiServer {
@@ -15,6 +15,7 @@ use once_cell::sync::{Lazy, OnceCell};
 use spdk_rs::{
     libspdk::{
         iovec,
+        spdk_bdev_flush_blocks,
         spdk_bdev_free_io,
         spdk_bdev_io,
         spdk_bdev_readv_blocks,
@@ -43,6 +44,7 @@ use crate::core::{
     DeviceEventSink,
     DeviceEventType,
     DeviceIoController,
+    GenericStatusCode,
     IoCompletionCallback,
     IoCompletionCallbackArg,
     IoCompletionStatus,
@@ -213,6 +215,10 @@ impl BlockDevice for SpdkBlockDevice {
     fn alignment(&self) -> u64 {
         self.bdev.alignment()
     }
+    /// the bdev layer splits IO to the device according to its own limits
+    fn max_io_size(&self) -> Option<u64> {
+        None
+    }
     /// returns true if the IO type is supported
     fn io_type_supported(&self, io_type: IoType) -> bool {
         self.bdev.io_type_supported(io_type)
@@ -294,6 +300,11 @@ fn io_type_to_err(
         IoType::Reset => CoreError::ResetDispatch {
             source,
         },
+        IoType::Flush => CoreError::FlushDispatch {
+            source,
+            offset,
+            len,
+        },
         _ => {
             warn!("Unsupported I/O operation: {:?}", op);
             CoreError::NotSupported {
@@ -362,6 +373,54 @@ extern "C" fn bdev_io_completion(
     }
 }
 
+/// Completes a FUA write by flushing the device it was written to, so that
+/// it only completes once the data is on stable storage.
+extern "C" fn bdev_fua_write_completion(
+    child_bio: *mut spdk_bdev_io,
+    success: bool,
+    ctx: *mut c_void,
+) {
+    if !success {
+        return bdev_io_completion(child_bio, success, ctx);
+    }
+
+    unsafe {
+        spdk_bdev_free_io(child_bio);
+    }
+
+    let bio = unsafe { &mut *(ctx as *mut IoCtx) };
+    let num_blocks = bio.handle.handle.get_bdev().num_blocks();
+    let (desc, chan) = bio.handle.handle.io_tuple();
+    let rc = unsafe {
+        spdk_bdev_flush_blocks(
+            desc,
+            chan,
+            0,
+            num_blocks,
+            Some(bdev_io_completion),
+            ctx,
+        )
+    };
+
+    if rc < 0 {
+        error!(
+            "{}: failed to flush FUA write: {}",
+            bio.handle.device.device_name(),
+            Errno::from_i32(-rc)
+        );
+        (bio.cb)(
+            &*bio.handle.device,
+            IoCompletionStatus::NvmeError(
+                NvmeCommandStatus::GenericCommandStatus(
+                    GenericStatusCode::InternalDeviceError,
+                ),
+            ),
+            bio.cb_arg,
+        );
+        free_bdev_io_ctx(&mut *bio);
+    }
+}
+
 #[async_trait(?Send)]
 impl BlockDeviceHandle for SpdkBlockDeviceHandle {
     fn get_device(&self) -> &dyn BlockDevice {
@@ -478,6 +537,52 @@ impl BlockDeviceHandle for SpdkBlockDeviceHandle {
         }
     }
 
+    fn writev_blocks_fua(
+        &self,
+        iov: *mut iovec,
+        iovcnt: i32,
+        offset_blocks: u64,
+        num_blocks: u64,
+        cb: IoCompletionCallback,
+        cb_arg: IoCompletionCallbackArg,
+    ) -> Result<(), CoreError> {
+        let ctx = alloc_bdev_io_ctx(
+            IoType::Write,
+            IoCtx {
+                handle: self,
+                cb,
+                cb_arg,
+            },
+            offset_blocks,
+            num_blocks,
+        )?;
+
+        // SPDK bdevs take no FUA, so the write is flushed once it completes
+        let (desc, chan) = self.handle.io_tuple();
+        let rc = unsafe {
+            spdk_bdev_writev_blocks(
+                desc,
+                chan,
+                iov,
+                iovcnt,
+                offset_blocks,
+                num_blocks,
+                Some(bdev_fua_write_completion),
+                ctx as *mut c_void,
+            )
+        };
+
+        if rc < 0 {
+            Err(CoreError::WriteDispatch {
+                source: Errno::from_i32(-rc),
+                offset: offset_blocks,
+                len: num_blocks,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
     fn reset(
         &self,
         cb: IoCompletionCallback,
@@ -513,6 +618,47 @@ impl BlockDeviceHandle for SpdkBlockDeviceHandle {
         }
     }
 
+    fn flush_io(
+        &self,
+        offset_blocks: u64,
+        num_blocks: u64,
+        cb: IoCompletionCallback,
+        cb_arg: IoCompletionCallbackArg,
+    ) -> Result<(), CoreError> {
+        let ctx = alloc_bdev_io_ctx(
+            IoType::Flush,
+            IoCtx {
+                handle: self,
+                cb,
+                cb_arg,
+            },
+            offset_blocks,
+            num_blocks,
+        )?;
+
+        let (desc, chan) = self.handle.io_tuple();
+        let rc = unsafe {
+            spdk_bdev_flush_blocks(
+                desc,
+                chan,
+                offset_blocks,
+                num_blocks,
+                Some(bdev_io_completion),
+                ctx as *mut c_void,
+            )
+        };
+
+        if rc < 0 {
+            Err(CoreError::FlushDispatch {
+                source: Errno::from_i32(-rc),
+                offset: offset_blocks,
+                len: num_blocks,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
     fn unmap_blocks(
         &self,
         offset_blocks: u64,
@@ -1,26 +1,41 @@
-use std::{collections::HashMap, convert::TryFrom};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    pin::Pin,
+    sync::Mutex,
+};
 
 use async_trait::async_trait;
+use futures::{future::Future, FutureExt};
+use once_cell::sync::Lazy;
 use snafu::ResultExt;
 use url::Url;
 
 use crate::{
     bdev::{
         dev::reject_unknown_parameters,
-        nexus::lookup_nexus_child,
+        nexus::{lookup_nexus_child, nexus_iter},
         util::uri,
         CreateDestroy,
         GetName,
     },
     core::Bdev,
+    jsonrpc::{jsonrpc_register, Code, JsonRpcError},
     nexus_uri::{self, NexusBdevError},
+    rebuild::RebuildJob,
 };
 
+/// aliases of the loopbacks which have been created and not destroyed
+static LOOPBACKS: Lazy<Mutex<HashSet<String>>> =
+    Lazy::new(|| Mutex::new(HashSet::new()));
+
 #[derive(Debug)]
 pub(super) struct Loopback {
     name: String,
     alias: String,
     uuid: Option<uuid::Uuid>,
+    /// accept the device even if its uuid does not match the given one
+    force_uuid: bool,
 }
 
 impl TryFrom<&Url> for Loopback {
@@ -45,12 +60,24 @@ impl TryFrom<&Url> for Loopback {
             },
         )?;
 
+        let force_uuid = match parameters.remove("force_uuid") {
+            Some(value) => uri::boolean(&value, true).context(
+                nexus_uri::BoolParamParseError {
+                    uri: url.to_string(),
+                    parameter: String::from("force_uuid"),
+                    value,
+                },
+            )?,
+            None => false,
+        };
+
         reject_unknown_parameters(url, parameters)?;
 
         Ok(Loopback {
             name: segments.join("/"),
             alias: url.to_string(),
             uuid,
+            force_uuid,
         })
     }
 }
@@ -67,11 +94,23 @@ impl CreateDestroy for Loopback {
 
     async fn create(&self) -> Result<String, Self::Error> {
         if let Some(mut bdev) = Bdev::lookup_by_name(&self.name) {
-            if self.uuid.is_some() && Some(bdev.uuid()) != self.uuid {
-                return Err(NexusBdevError::BdevWrongUuid {
-                    name: self.get_name(),
-                    uuid: bdev.uuid_as_string(),
-                });
+            match self.uuid {
+                Some(uuid) if uuid != bdev.uuid() => {
+                    if !self.force_uuid {
+                        return Err(NexusBdevError::BdevWrongUuid {
+                            name: self.get_name(),
+                            uuid: bdev.uuid_as_string(),
+                        });
+                    }
+                    // the uuid belongs to the device, it is not changed
+                    warn!(
+                        "device {} has uuid {} instead of {}, using it as is",
+                        self.get_name(),
+                        bdev.uuid_as_string(),
+                        uuid
+                    );
+                }
+                _ => {}
             }
 
             if !bdev.as_mut().add_alias(&self.alias) {
@@ -81,6 +120,10 @@ impl CreateDestroy for Loopback {
                     self.get_name()
                 );
             }
+            LOOPBACKS
+                .lock()
+                .expect("lock poisoned")
+                .insert(self.alias.clone());
 
             return Ok(self.get_name());
         }
@@ -91,6 +134,7 @@ impl CreateDestroy for Loopback {
     }
 
     async fn destroy(self: Box<Self>) -> Result<(), Self::Error> {
+        LOOPBACKS.lock().expect("lock poisoned").remove(&self.alias);
         if let Some(child) = lookup_nexus_child(&self.name) {
             child.remove();
         }
@@ -100,3 +144,94 @@ impl CreateDestroy for Loopback {
         Ok(())
     }
 }
+
+/// Returns true if the alias was added by a loopback uri.
+fn is_loopback_alias(alias: &str) -> bool {
+    Url::parse(alias).map_or(false, |url| {
+        url.scheme() == "bdev" || url.scheme() == "loopback"
+    })
+}
+
+/// Returns true if the alias belongs to a loopback which still exists, is
+/// the name of a child of any nexus or is the device of a rebuild job.
+fn alias_in_use(alias: &str) -> bool {
+    LOOPBACKS.lock().expect("lock poisoned").contains(alias)
+        || nexus_iter()
+            .any(|nexus| nexus.children.iter().any(|c| c.name == alias))
+        || RebuildJob::lookup(alias).is_ok()
+        || !RebuildJob::lookup_src(alias).is_empty()
+}
+
+/// Returns the loopback aliases of the given device.
+pub fn list_aliases(name: &str) -> Result<Vec<String>, NexusBdevError> {
+    let bdev = Bdev::lookup_by_name(name).ok_or_else(|| {
+        NexusBdevError::BdevNotFound {
+            name: name.to_string(),
+        }
+    })?;
+    Ok(bdev
+        .as_ref()
+        .aliases()
+        .into_iter()
+        .filter(|alias| is_loopback_alias(alias))
+        .collect())
+}
+
+/// Removes the loopback aliases of the given device which are no longer
+/// used, e.g. because they were left behind by a loopback which no longer
+/// exists. Returns the removed aliases.
+pub fn prune_aliases(name: &str) -> Result<Vec<String>, NexusBdevError> {
+    let stale = list_aliases(name)?
+        .into_iter()
+        .filter(|alias| !alias_in_use(alias))
+        .collect::<Vec<_>>();
+
+    if let Some(mut bdev) = Bdev::lookup_by_name(name) {
+        for alias in &stale {
+            info!("removing stale alias {} of device {}", alias, name);
+            bdev.as_mut().remove_alias(alias);
+        }
+    }
+    Ok(stale)
+}
+
+/// Arguments of the loopback alias json-rpc methods
+#[derive(Deserialize)]
+struct LoopbackAliasArgs {
+    /// name of the device
+    name: String,
+}
+
+/// Maps a device error to the json-rpc error returned to the caller.
+fn rpc_error(error: NexusBdevError) -> JsonRpcError {
+    let code = match error {
+        NexusBdevError::BdevNotFound {
+            ..
+        } => Code::NotFound,
+        _ => Code::InternalError,
+    };
+    JsonRpcError::new(code, error)
+}
+
+/// Registers the json-rpc methods to list and prune loopback aliases.
+pub(crate) fn register_rpc() {
+    jsonrpc_register(
+        "loopback_list_aliases",
+        |args: LoopbackAliasArgs| -> Pin<
+            Box<dyn Future<Output = Result<Vec<String>, JsonRpcError>>>,
+        > {
+            let f = async move { list_aliases(&args.name).map_err(rpc_error) };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "loopback_prune_aliases",
+        |args: LoopbackAliasArgs| -> Pin<
+            Box<dyn Future<Output = Result<Vec<String>, JsonRpcError>>>,
+        > {
+            let f = async move { prune_aliases(&args.name).map_err(rpc_error) };
+            Box::pin(f.boxed_local())
+        },
+    );
+}
@@ -2,6 +2,8 @@ use async_trait::async_trait;
 
 pub use dev::{device_create, device_destroy, device_lookup, device_open};
 pub use device::{bdev_io_ctx_pool_init, SpdkBlockDevice};
+pub(crate) use loopback::register_rpc as register_loopback_rpc;
+pub use loopback::{list_aliases, prune_aliases};
 pub use nexus::{Nexus, NexusInfo, NexusState};
 pub use nvmx::{
     nvme_io_ctx_pool_init,
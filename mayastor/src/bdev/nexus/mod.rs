@@ -1,33 +1,56 @@
 #![allow(clippy::vec_box)]
 
-use futures::{future::Future, FutureExt};
+use futures::{
+    future::{join_all, Future},
+    FutureExt,
+};
+use rpc::mayastor::ShareProtocolNexus;
 use std::pin::Pin;
 
+use crate::{
+    core::{Bdev, Share},
+    jsonrpc::{jsonrpc_register, Code, JsonRpcError, Result},
+    rebuild::RebuildJob,
+    subsys::{NvmfError, NvmfSubsystem},
+};
+
 mod nexus_bdev;
 mod nexus_bdev_children;
 mod nexus_bdev_rebuild;
 mod nexus_bdev_snapshot;
 mod nexus_channel;
 mod nexus_child;
+mod nexus_heal;
+mod nexus_health;
 mod nexus_io;
 mod nexus_iter;
 mod nexus_module;
 mod nexus_nbd;
 mod nexus_persistence;
+mod nexus_reconnect;
 mod nexus_share;
+mod nexus_size;
 
 pub use nexus_bdev::{
     nexus_create,
+    nexus_create_assume_clean,
     nexus_create_v2,
+    nexus_create_with_replica_policy,
+    validate_cntlid_range,
     Error,
     Nexus,
     NexusNvmeParams,
+    NexusReplicaPolicy,
     NexusState,
     NexusStatus,
     NexusTarget,
+    NexusTrimPolicy,
+    OnInsufficientReplicas,
     VerboseError,
+    WriteAckPolicy,
 };
 pub(crate) use nexus_bdev::{
+    ChildResvPreemptFailed,
     CreateChild,
     CreateRebuild,
     OpenChild,
@@ -40,6 +63,7 @@ pub(crate) use nexus_bdev::{
     UnshareNexus,
     NEXUS_PRODUCT_ID,
 };
+pub use nexus_bdev_rebuild::{ActiveRebuild, RebuildRecord};
 pub(crate) use nexus_channel::{
     fault_nexus_child,
     DrEvent,
@@ -50,9 +74,13 @@ pub use nexus_child::{
     lookup_nexus_child,
     ChildError,
     ChildState,
+    ChildStateTransition,
     NexusChild,
     Reason,
 };
+pub use nexus_heal::{set_child_replacer, ChildReplacer, NexusHealPolicy};
+pub use nexus_health::{ChildHealth, ChildHealthEntry, NexusHealth};
+pub(crate) use nexus_health::{HEALTH_LOG_PAGE, HEALTH_LOG_PAGE_SIZE};
 pub(crate) use nexus_io::{nexus_submit_request, NioCtx};
 pub use nexus_iter::{
     nexus_iter,
@@ -66,6 +94,8 @@ pub(crate) use nexus_module::{NexusModule, NEXUS_MODULE_NAME};
 pub(crate) use nexus_nbd::{NbdDisk, NbdError};
 pub(crate) use nexus_persistence::PersistOp;
 pub use nexus_persistence::{ChildInfo, NexusInfo};
+pub use nexus_reconnect::{reconnect_delays, reconnect_wanted, ChildReconnect};
+pub use nexus_size::{ChildSizeReconcile, UnreconciledChild};
 
 /// TODO
 #[derive(Deserialize)]
@@ -78,6 +108,65 @@ struct NexusShareArgs {
     cntlid_min: u16,
     /// TODO
     cntlid_max: u16,
+    /// seconds the initiator waits between reconnect attempts (nvmf only)
+    reconnect_delay: Option<u32>,
+    /// reconnect attempts before the initiator gives up (nvmf only)
+    max_reconnects: Option<u32>,
+    /// number of paths to export the nexus on for multipath (nvmf only)
+    num_paths: Option<usize>,
+}
+
+/// reconnect delay used by the linux initiator when none is given
+const NVMF_DEFAULT_RECONNECT_DELAY: u32 = 10;
+
+impl NexusShareArgs {
+    /// Validates the reconnect tuning and paths, which only apply to nvmf.
+    fn validate_reconnect(&self) -> Result<()> {
+        if self.reconnect_delay.is_none()
+            && self.max_reconnects.is_none()
+            && self.num_paths.is_none()
+        {
+            return Ok(());
+        }
+        if self.protocol != "nvmf" {
+            return Err(JsonRpcError {
+                code: Code::InvalidParams,
+                message:
+                    "reconnect tuning and paths are only supported for nvmf"
+                        .to_string(),
+            });
+        }
+        if self.reconnect_delay == Some(0) {
+            return Err(JsonRpcError {
+                code: Code::InvalidParams,
+                message: "reconnect_delay must be at least 1s".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Appends the reconnect tuning to the share uri as query parameters so
+    /// that the initiator can pass them on when connecting: the controller
+    /// loss timeout is the total time spent on reconnect attempts.
+    fn reconnect_uri(&self, uri: String) -> String {
+        let mut url = match url::Url::parse(&uri) {
+            Ok(url) => url,
+            Err(_) => return uri,
+        };
+        let delay =
+            self.reconnect_delay.unwrap_or(NVMF_DEFAULT_RECONNECT_DELAY);
+        if self.reconnect_delay.is_some() {
+            url.query_pairs_mut()
+                .append_pair("reconnect_delay", &delay.to_string());
+        }
+        if let Some(attempts) = self.max_reconnects {
+            url.query_pairs_mut().append_pair(
+                "ctrl_loss_tmo",
+                &attempts.saturating_mul(delay).to_string(),
+            );
+        }
+        url.to_string()
+    }
 }
 
 /// TODO
@@ -85,44 +174,279 @@ struct NexusShareArgs {
 struct NexusShareReply {
     /// TODO
     uri: String,
+    /// uris of all paths the nexus is shared on
+    uris: Vec<String>,
+}
+
+/// Exports the shared bdev over the given number of nvmf paths.
+async fn nvmf_set_num_paths(bdev: &Bdev, num_paths: usize) -> Result<()> {
+    let subsystem =
+        NvmfSubsystem::nqn_lookup(&bdev.name()).ok_or_else(|| {
+            JsonRpcError {
+                code: Code::NotFound,
+                message: "nvmf subsystem not found".to_string(),
+            }
+        })?;
+    subsystem.set_num_paths(num_paths).await.map_err(|e| {
+        let code = match e {
+            NvmfError::Paths {
+                ..
+            } => Code::InvalidParams,
+            _ => Code::InternalError,
+        };
+        JsonRpcError {
+            code,
+            message: e.to_string(),
+        }
+    })
+}
+
+/// Summary of a nexus returned by the nexus_list json-rpc method
+#[derive(Serialize)]
+struct NexusListEntry {
+    /// name of the nexus
+    name: String,
+    /// uuid of the nexus
+    uuid: String,
+    /// size of the nexus in bytes
+    size: u64,
+    /// rolled-up status of the nexus
+    status: NexusStatus,
+    /// number of children
+    children: usize,
+    /// number of children being rebuilt
+    rebuilds: usize,
+    /// protocol the nexus is shared with: nbd, iscsi or nvmf
+    share_protocol: Option<String>,
+    /// uri the nexus is shared on
+    share_uri: Option<String>,
+}
+
+impl From<&Nexus<'_>> for NexusListEntry {
+    fn from(nexus: &Nexus) -> Self {
+        NexusListEntry {
+            name: nexus.name.clone(),
+            uuid: nexus.uuid().to_string(),
+            size: nexus.size_in_bytes(),
+            status: nexus.status(),
+            children: nexus.children.len(),
+            rebuilds: nexus
+                .children
+                .iter()
+                .filter(|c| RebuildJob::lookup(&c.name).is_ok())
+                .count(),
+            share_protocol: nexus.nexus_target.as_ref().map(|t| {
+                match t {
+                    NexusTarget::NbdDisk(_) => "nbd",
+                    NexusTarget::NexusIscsiTarget => "iscsi",
+                    NexusTarget::NexusNvmfTarget => "nvmf",
+                }
+                .to_string()
+            }),
+            share_uri: nexus.get_share_uri(),
+        }
+    }
+}
+
+/// Arguments of the nexus_create json-rpc method
+#[derive(Deserialize)]
+struct NexusCreateArgs {
+    /// name of the nexus
+    name: String,
+    /// size of the nexus in bytes
+    size: u64,
+    /// uuid of the nexus bdev
+    #[serde(default)]
+    uuid: Option<String>,
+    /// uris of the children
+    children: Vec<String>,
+    /// trust the data of all children, so that none of them is rebuilt
+    #[serde(default)]
+    assume_clean: bool,
+}
+
+/// Arguments of the nexus_reshare json-rpc method
+#[derive(Deserialize)]
+struct NexusReshareArgs {
+    /// name of the nexus
+    name: String,
+    /// new protocol: nbd, iscsi or nvmf
+    protocol: String,
+}
+
+/// Arguments of the nexus_set_replica_policy json-rpc method
+#[derive(Deserialize)]
+struct NexusReplicaPolicyArgs {
+    /// name of the nexus
+    name: String,
+    /// the replica policy to apply
+    #[serde(flatten)]
+    policy: NexusReplicaPolicy,
+}
+
+/// Arguments of the nexus_set_heal_policy json-rpc method
+#[derive(Deserialize)]
+struct NexusHealPolicyArgs {
+    /// name of the nexus
+    name: String,
+    /// the heal policy to apply
+    #[serde(flatten)]
+    policy: NexusHealPolicy,
+}
+
+/// Arguments of the nexus_set_trim_policy json-rpc method
+#[derive(Deserialize)]
+struct NexusTrimPolicyArgs {
+    /// name of the nexus
+    name: String,
+    /// the trim policy to apply
+    policy: NexusTrimPolicy,
+}
+
+/// Arguments of the nexus_shrink json-rpc method
+#[derive(Deserialize)]
+struct NexusShrinkArgs {
+    /// name of the nexus
+    name: String,
+    /// new size of the nexus in bytes
+    size: u64,
+    /// shrink even if the tail which is cut off holds data
+    #[serde(default)]
+    force: bool,
+}
+
+/// Arguments of the nexus_set_write_ack_policy json-rpc method
+#[derive(Deserialize)]
+struct NexusWriteAckPolicyArgs {
+    /// name of the nexus
+    name: String,
+    /// the write ack policy to apply
+    policy: WriteAckPolicy,
+}
+
+/// Arguments of the nexus_set_max_rebuilds json-rpc method
+#[derive(Deserialize)]
+struct NexusMaxRebuildsArgs {
+    /// rebuilds which may run at once across all nexuses, 0 for no limit
+    max_rebuilds: usize,
+}
+
+/// Arguments of the nexus_rebuild_with_source json-rpc method
+#[derive(Deserialize)]
+struct NexusRebuildWithSourceArgs {
+    /// name of the nexus
+    name: String,
+    /// child to rebuild
+    child: String,
+    /// healthy child to copy the data from
+    source: String,
+}
+
+/// Arguments of json-rpc methods which only take a nexus
+#[derive(Deserialize)]
+struct NexusNameArgs {
+    /// name of the nexus
+    name: String,
+}
+
+/// Arguments of json-rpc methods which operate on a nexus child
+#[derive(Deserialize)]
+struct NexusChildArgs {
+    /// name of the nexus
+    name: String,
+    /// name of the child
+    child: String,
+}
+
+/// Arguments of the nexus_resv_preempt_batch json-rpc method
+#[derive(Deserialize)]
+struct NexusResvPreemptBatchArgs {
+    /// preempt operations, each on its own nexus
+    operations: Vec<NexusResvPreemptArgs>,
+}
+
+/// Outcome of one operation of the nexus_resv_preempt_batch json-rpc method
+#[derive(Serialize)]
+struct NexusResvBatchResult {
+    /// name of the nexus
+    name: String,
+    /// why the operation failed, if it did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Arguments of the nexus_resv_preempt json-rpc method, and of each
+/// operation of nexus_resv_preempt_batch
+#[derive(Deserialize)]
+struct NexusResvPreemptArgs {
+    /// name of the nexus
+    name: String,
+    /// our registered reservation key
+    key: u64,
+    /// reservation key of the host to preempt
+    preempt_key: u64,
+    /// NVMe reservation type to acquire
+    resv_type: u8,
+}
+
+/// Looks up a nexus by its name, failing with a NotFound error.
+fn nexus_lookup_rpc<'n>(name: &str) -> Result<Pin<&'n mut Nexus<'n>>, Error> {
+    nexus_lookup_mut(name).ok_or_else(|| Error::NexusNotFound {
+        name: name.to_string(),
+    })
 }
 
 /// public function which simply calls register module
 pub fn register_module() {
     nexus_module::register_module();
 
-    use crate::{
-        core::{Bdev, Share},
-        jsonrpc::{jsonrpc_register, Code, JsonRpcError, Result},
-    };
-
     jsonrpc_register(
         "nexus_share",
         |args: NexusShareArgs| -> Pin<Box<dyn Future<Output = Result<NexusShareReply>>>> {
             // FIXME: shares bdev, not a nexus
             let f = async move {
-                let proto = args.protocol;
+                let proto = args.protocol.clone();
                 if proto != "iscsi" && proto != "nvmf" {
                     return Err(JsonRpcError {
                         code: Code::InvalidParams,
                         message: "invalid protocol".to_string(),
                     });
                 }
+                args.validate_reconnect()?;
                 if let Some(bdev) = Bdev::lookup_by_name(&args.name) {
                     match proto.as_str() {
                         "nvmf" => {
-                            bdev.share_nvmf(Some((args.cntlid_min, args.cntlid_max)))
+                            validate_cntlid_range(
+                                &args.name,
+                                args.cntlid_min,
+                                args.cntlid_max,
+                                args.num_paths.unwrap_or(1),
+                            )
+                            .map_err(|e| {
+                                JsonRpcError::new(Code::InvalidParams, e)
+                            })?;
+                            let share = bdev
+                                .share_nvmf(Some((args.cntlid_min, args.cntlid_max)))
                                 .await
                                 .map_err(|e| {
                                     JsonRpcError {
                                         code: Code::InternalError,
                                         message: e.to_string(),
                                     }
-                                })
-                                .map(|share| {
-                                    NexusShareReply {
-                                        uri: bdev.share_uri().unwrap_or(share),
-                                }
+                                })?;
+                            if let Some(num_paths) = args.num_paths {
+                                nvmf_set_num_paths(&bdev, num_paths).await?;
+                            }
+                            Ok(NexusShareReply {
+                                uri: args.reconnect_uri(
+                                    bdev.share_uri().unwrap_or(share),
+                                ),
+                                uris: NvmfSubsystem::nqn_lookup(&bdev.name())
+                                    .and_then(|ss| ss.uri_endpoints())
+                                    .unwrap_or_default()
+                                    .into_iter()
+                                    .map(|uri| args.reconnect_uri(uri))
+                                    .collect(),
                             })
                         },
                         "iscsi" => {
@@ -135,8 +459,10 @@ pub fn register_module() {
                                     }
                                 })
                                 .map(|share| {
+                                    let uri = bdev.share_uri().unwrap_or(share);
                                     NexusShareReply {
-                                        uri: bdev.share_uri().unwrap_or(share),
+                                        uris: vec![uri.clone()],
+                                        uri,
                                 }
                             })
                         },
@@ -152,6 +478,356 @@ pub fn register_module() {
             Box::pin(f.boxed_local())
         },
     );
+
+    jsonrpc_register(
+        "nexus_list",
+        |_: ()| -> Pin<
+            Box<dyn Future<Output = Result<Vec<NexusListEntry>, Error>>>,
+        > {
+            let f = async move {
+                Ok(nexus_iter().map(|n| NexusListEntry::from(&*n)).collect())
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_create",
+        |args: NexusCreateArgs| -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
+            let f = async move {
+                let uuid = args.uuid.as_deref();
+                if args.assume_clean {
+                    nexus_create_assume_clean(
+                        &args.name,
+                        args.size,
+                        uuid,
+                        &args.children,
+                    )
+                    .await
+                } else {
+                    nexus_create(&args.name, args.size, uuid, &args.children)
+                        .await
+                }
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_reshare",
+        |args: NexusReshareArgs| -> Pin<Box<dyn Future<Output = Result<NexusShareReply, Error>>>> {
+            let f = async move {
+                let protocol = match args.protocol.as_str() {
+                    "nbd" => ShareProtocolNexus::NexusNbd,
+                    "iscsi" => ShareProtocolNexus::NexusIscsi,
+                    "nvmf" => ShareProtocolNexus::NexusNvmf,
+                    _ => {
+                        return Err(Error::InvalidArguments {
+                            name: args.name,
+                            args: format!("invalid protocol {}", args.protocol),
+                        })
+                    }
+                };
+                let nexus = nexus_lookup_rpc(&args.name)?;
+                let uri = nexus.reshare(protocol, None).await?;
+                Ok(NexusShareReply {
+                    uris: vec![uri.clone()],
+                    uri,
+                })
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_set_replica_policy",
+        |args: NexusReplicaPolicyArgs| -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
+            let f = async move {
+                nexus_lookup_rpc(&args.name)?.set_replica_policy(args.policy)
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_get_replica_policy",
+        |args: NexusNameArgs| -> Pin<
+            Box<dyn Future<Output = Result<NexusReplicaPolicy, Error>>>,
+        > {
+            let f = async move {
+                Ok(nexus_lookup_rpc(&args.name)?.replica_policy())
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_set_heal_policy",
+        |args: NexusHealPolicyArgs| -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
+            let f = async move {
+                nexus_lookup_rpc(&args.name)?.set_heal_policy(args.policy);
+                Ok(())
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_set_trim_policy",
+        |args: NexusTrimPolicyArgs| -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
+            let f = async move {
+                nexus_lookup_rpc(&args.name)?.set_trim_policy(args.policy);
+                Ok(())
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_shrink",
+        |args: NexusShrinkArgs| -> Pin<
+            Box<dyn Future<Output = Result<Vec<UnreconciledChild>, Error>>>,
+        > {
+            let f = async move {
+                nexus_lookup_rpc(&args.name)?
+                    .shrink(args.size, args.force)
+                    .await
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_set_write_ack_policy",
+        |args: NexusWriteAckPolicyArgs| -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
+            let f = async move {
+                nexus_lookup_rpc(&args.name)?.set_write_ack_policy(args.policy);
+                Ok(())
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_set_max_rebuilds",
+        |args: NexusMaxRebuildsArgs| -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
+            let f = async move {
+                Nexus::set_max_rebuilds(args.max_rebuilds).await;
+                Ok(())
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_rebuild_with_source",
+        |args: NexusRebuildWithSourceArgs| -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
+            let f = async move {
+                nexus_lookup_rpc(&args.name)?
+                    .rebuild_with_source(&args.child, &args.source)
+                    .await
+                    .map(|_| ())
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_resv_preempt",
+        |args: NexusResvPreemptArgs| -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
+            let f = async move {
+                nexus_lookup_rpc(&args.name)?
+                    .resv_preempt(args.key, args.preempt_key, args.resv_type)
+                    .await
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_resv_preempt_batch",
+        |args: NexusResvPreemptBatchArgs| -> Pin<
+            Box<dyn Future<Output = Result<Vec<NexusResvBatchResult>, Error>>>,
+        > {
+            let f = async move {
+                // a failure on one nexus must not stop the others
+                let operations =
+                    args.operations.into_iter().map(|op| async move {
+                        let result = match nexus_lookup_rpc(&op.name) {
+                            Ok(nexus) => {
+                                nexus
+                                    .resv_preempt(
+                                        op.key,
+                                        op.preempt_key,
+                                        op.resv_type,
+                                    )
+                                    .await
+                            }
+                            Err(error) => Err(error),
+                        };
+                        NexusResvBatchResult {
+                            name: op.name,
+                            error: result.err().map(|error| error.verbose()),
+                        }
+                    });
+                Ok(join_all(operations).await)
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_offline_child",
+        |args: NexusChildArgs| -> Pin<Box<dyn Future<Output = Result<NexusStatus, Error>>>> {
+            let f = async move {
+                nexus_lookup_rpc(&args.name)?.offline_child(&args.child).await
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_online_child",
+        |args: NexusChildArgs| -> Pin<Box<dyn Future<Output = Result<NexusStatus, Error>>>> {
+            let f = async move {
+                nexus_lookup_rpc(&args.name)?.online_child(&args.child).await
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_child_state_history",
+        |args: NexusChildArgs| -> Pin<
+            Box<dyn Future<Output = Result<Vec<ChildStateTransition>, Error>>>,
+        > {
+            let f = async move {
+                let nexus = nexus_lookup_rpc(&args.name)?;
+                Ok(nexus.get_child_by_name(&args.child)?.state_history())
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_promote_read_primary",
+        |args: NexusChildArgs| -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
+            let f = async move {
+                nexus_lookup_rpc(&args.name)?
+                    .promote_read_primary(&args.child)
+                    .await
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_demote_read_primary",
+        |args: NexusChildArgs| -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
+            let f = async move {
+                nexus_lookup_rpc(&args.name)?
+                    .demote_read_primary(&args.child)
+                    .await
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_child_health",
+        |args: NexusChildArgs| -> Pin<Box<dyn Future<Output = Result<ChildHealth, Error>>>> {
+            let f = async move {
+                nexus_lookup_rpc(&args.name)?.child_health(&args.child).await
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_health",
+        |args: NexusNameArgs| -> Pin<Box<dyn Future<Output = Result<NexusHealth, Error>>>> {
+            let f = async move {
+                Ok(nexus_lookup_rpc(&args.name)?.health().await)
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_child_reconnects",
+        |args: NexusNameArgs| -> Pin<
+            Box<dyn Future<Output = Result<Vec<ChildReconnect>, Error>>>,
+        > {
+            let f = async move {
+                Ok(nexus_lookup_rpc(&args.name)?.child_reconnects())
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_probe_child",
+        |args: NexusChildArgs| -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
+            let f = async move {
+                nexus_lookup_rpc(&args.name)?.probe_child(&args.child).await
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_reconcile_child_sizes",
+        |args: NexusNameArgs| -> Pin<
+            Box<dyn Future<Output = Result<ChildSizeReconcile, Error>>>,
+        > {
+            let f = async move {
+                Ok(nexus_lookup_rpc(&args.name)?.reconcile_child_sizes().await)
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_list_rebuilds",
+        |_: ()| -> Pin<
+            Box<dyn Future<Output = Result<Vec<ActiveRebuild>, Error>>>,
+        > {
+            let f = async move { Ok(Nexus::list_rebuilds()) };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_cancel_rebuild",
+        |args: NexusChildArgs| -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
+            let f = async move {
+                nexus_lookup_rpc(&args.name)?.cancel_rebuild(&args.child)
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_cancel_all_rebuilds",
+        |_: ()| -> Pin<
+            Box<dyn Future<Output = Result<Vec<ActiveRebuild>, Error>>>,
+        > {
+            let f = async move { Ok(Nexus::cancel_all_rebuilds()) };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_rebuild_history",
+        |args: NexusNameArgs| -> Pin<
+            Box<dyn Future<Output = Result<Vec<RebuildRecord>, Error>>>,
+        > {
+            let f = async move {
+                Ok(nexus_lookup_rpc(&args.name)?.rebuild_history())
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
 }
 
 /// called during shutdown so that all nexus children are in Destroying state
@@ -160,7 +836,10 @@ pub async fn nexus_children_to_destroying_state() {
     info!("setting all nexus children to destroying state...");
     for nexus in nexus_iter() {
         for child in nexus.children.iter() {
-            child.set_state(nexus_child::ChildState::Destroying);
+            child.set_state_cause(
+                nexus_child::ChildState::Destroying,
+                Some("shutdown"),
+            );
         }
     }
     info!("set all nexus children to destroying state");
@@ -5,18 +5,22 @@
 //! application needs synchronous mirroring may be required.
 
 use std::{
+    cmp::min,
+    collections::{HashMap, VecDeque},
     fmt::{Display, Formatter},
     marker::PhantomPinned,
     os::raw::c_void,
     pin::Pin,
     ptr::NonNull,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 use crossbeam::atomic::AtomicCell;
 use futures::channel::oneshot;
 use nix::errno::Errno;
 use rpc::mayastor::NvmeAnaState;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
 use tonic::{Code, Status};
 use uuid::Uuid;
@@ -25,21 +29,26 @@ use super::{
     nexus_lookup_name_uuid,
     nexus_submit_request,
     ChildError,
+    ChildReconnect,
     ChildState,
     DrEvent,
     NbdDisk,
     NbdError,
     NexusChannel,
     NexusChild,
+    NexusHealPolicy,
     NexusInfo,
     NexusModule,
     PersistOp,
+    RebuildRecord,
+    UnreconciledChild,
 };
 
 use crate::{
     bdev::device_destroy,
     core::{
         Bdev,
+        BdevHandle,
         Command,
         CoreError,
         Cores,
@@ -50,13 +59,15 @@ use crate::{
         Share,
         MWQ,
     },
+    jsonrpc::{Code as JsonRpcCode, RpcErrorCode},
     nexus_uri::NexusBdevError,
-    rebuild::RebuildError,
-    subsys::{NvmfError, NvmfSubsystem},
+    rebuild::{RebuildError, RebuildMap},
+    sleep::mayastor_sleep,
+    subsys::{Config, NvmfError, NvmfSubsystem},
 };
 
 use spdk_rs::{
-    libspdk::spdk_bdev,
+    libspdk::{spdk_bdev, spdk_bdev_notify_blockcnt_change},
     BdevIo,
     BdevOps,
     ChannelTraverseStatus,
@@ -179,6 +190,40 @@ pub enum Error {
         child: String,
         name: String,
     },
+    #[snafu(display(
+        "Failed to preempt reservation key {:x}h on child {} of nexus {}",
+        preempt_key,
+        child,
+        name
+    ))]
+    ChildResvPreemptFailed {
+        source: ChildError,
+        preempt_key: u64,
+        child: String,
+        name: String,
+    },
+    #[snafu(display(
+        "Failed to read the health of child {} of nexus {}",
+        child,
+        name
+    ))]
+    ChildHealth {
+        source: ChildError,
+        child: String,
+        name: String,
+    },
+    #[snafu(display("Failed to probe child {} of nexus {}", child, name))]
+    ChildProbe {
+        source: ChildError,
+        child: String,
+        name: String,
+    },
+    #[snafu(display(
+        "Cannot probe child {} of nexus {} which is not open or has no metadata reservation",
+        child,
+        name
+    ))]
+    ChildProbeUnavailable { child: String, name: String },
     #[snafu(display("Failed to open child {} of nexus {}", child, name))]
     OpenChild {
         source: ChildError,
@@ -229,6 +274,17 @@ pub enum Error {
     PauseChild { child: String, name: String },
     #[snafu(display("Suitable rebuild source for nexus {} not found", name))]
     NoRebuildSource { name: String },
+    #[snafu(display(
+        "Child {} of nexus {} cannot be used as a rebuild source in state {}",
+        child,
+        name,
+        state
+    ))]
+    InvalidRebuildSource {
+        child: String,
+        name: String,
+        state: String,
+    },
     #[snafu(display(
         "Failed to create rebuild job for child {} of nexus {}",
         child,
@@ -269,6 +325,17 @@ pub enum Error {
         name: String,
         source: RebuildError,
     },
+    #[snafu(display(
+        "Cannot rebuild child {} of nexus {}: {} rebuilds already running",
+        child,
+        name,
+        limit,
+    ))]
+    RebuildLimitReached {
+        child: String,
+        name: String,
+        limit: usize,
+    },
     #[snafu(display("Invalid ShareProtocol value {}", sp_value))]
     InvalidShareProtocol { sp_value: i32 },
     #[snafu(display("Invalid NvmeAnaState value {}", ana_value))]
@@ -290,6 +357,31 @@ pub enum Error {
         name: String,
         state: String,
     },
+    #[snafu(display(
+        "Child {} of nexus {} cannot be the read primary, it is {}",
+        child,
+        name,
+        state
+    ))]
+    ReadPrimaryNotOpen {
+        child: String,
+        name: String,
+        state: String,
+    },
+    #[snafu(display(
+        "Nexus {} holds data at offset {} beyond the new size",
+        name,
+        offset
+    ))]
+    ShrinkTailInUse { name: String, offset: u64 },
+    #[snafu(display(
+        "Failed to scan the tail of nexus {} before shrinking: {}",
+        name,
+        reason
+    ))]
+    ShrinkScan { name: String, reason: String },
+    #[snafu(display("Failed to resize nexus {}: {}", name, source))]
+    ShrinkNotify { source: Errno, name: String },
     #[snafu(display("Failed to get BdevHandle for snapshot operation"))]
     FailedGetHandle,
     #[snafu(display("Failed to create snapshot on nexus {}", name))]
@@ -311,6 +403,26 @@ impl From<NvmfError> for Error {
     }
 }
 
+impl RpcErrorCode for Error {
+    fn rpc_error_code(&self) -> JsonRpcCode {
+        match self {
+            Error::NexusNotFound {
+                ..
+            } => JsonRpcCode::NotFound,
+            Error::ChildNotFound {
+                ..
+            } => JsonRpcCode::NotFound,
+            Error::InvalidArguments {
+                ..
+            } => JsonRpcCode::InvalidParams,
+            Error::ShrinkTailInUse {
+                ..
+            } => JsonRpcCode::InvalidParams,
+            _ => JsonRpcCode::InternalError,
+        }
+    }
+}
+
 impl From<Error> for tonic::Status {
     fn from(e: Error) -> Self {
         match e {
@@ -350,6 +462,9 @@ impl From<Error> for tonic::Status {
             Error::ChildNotFound {
                 ..
             } => Status::not_found(e.to_string()),
+            Error::RebuildLimitReached {
+                ..
+            } => Status::resource_exhausted(e.to_string()),
             e => Status::new(Code::Internal, e.to_string()),
         }
     }
@@ -357,6 +472,10 @@ impl From<Error> for tonic::Status {
 
 pub(crate) static NEXUS_PRODUCT_ID: &str = "Nexus CAS Driver v0.0.1";
 
+/// size of the reads used to check that the tail of a nexus is unused
+/// before shrinking it
+const SHRINK_SCAN_SIZE: u64 = 1024 * 1024;
+
 #[derive(Debug)]
 pub enum NexusTarget {
     NbdDisk(NbdDisk),
@@ -413,6 +532,93 @@ impl NexusNvmeParams {
     }
 }
 
+/// Action taken by the nexus when fewer children are healthy than its
+/// replica policy requires.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnInsufficientReplicas {
+    /// fail all IO submitted to the nexus
+    Fault,
+    /// keep serving reads from the remaining children but reject writes
+    ReadOnly,
+}
+
+/// Policy describing how many healthy children the nexus needs to serve IO
+/// normally and what to do when it has fewer than that.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct NexusReplicaPolicy {
+    /// minimum number of healthy children
+    pub min_healthy: u32,
+    /// action taken when less than `min_healthy` children are healthy
+    pub on_insufficient: OnInsufficientReplicas,
+}
+
+impl NexusReplicaPolicy {
+    /// Checks that the minimum number of healthy children can be met by a
+    /// nexus with the given number of children.
+    fn validate(&self, name: &str, num_children: usize) -> Result<(), Error> {
+        let args = if self.min_healthy == 0 {
+            "minimum number of healthy children must be at least 1".to_string()
+        } else if self.min_healthy as usize > num_children {
+            format!(
+                "minimum number of healthy children {} exceeds the number of children {}",
+                self.min_healthy, num_children
+            )
+        } else {
+            return Ok(());
+        };
+        Err(Error::InvalidArguments {
+            name: name.to_owned(),
+            args,
+        })
+    }
+}
+
+impl Default for NexusReplicaPolicy {
+    fn default() -> Self {
+        Self {
+            min_healthy: 1,
+            on_insufficient: OnInsufficientReplicas::Fault,
+        }
+    }
+}
+
+/// How the nexus handles unmap (trim) requests from its clients.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NexusTrimPolicy {
+    /// unmap all children, unmap is only supported if every child does
+    PropagateAll,
+    /// complete unmaps without passing them on to any child
+    Drop,
+    /// unmap those children which support it and skip the others
+    BestEffort,
+}
+
+impl Default for NexusTrimPolicy {
+    fn default() -> Self {
+        Self::PropagateAll
+    }
+}
+
+/// When the nexus acknowledges a write which failed on some of its children.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteAckPolicy {
+    /// only acknowledge writes which succeeded on every child, others are
+    /// retried on the remaining children or failed
+    AckOnAll,
+    /// acknowledge writes which succeeded on a majority of the children,
+    /// the children they failed on are faulted
+    AckOnQuorum,
+}
+
+impl Default for WriteAckPolicy {
+    fn default() -> Self {
+        Self::AckOnAll
+    }
+}
+
 /// The main nexus structure
 #[derive(Debug)]
 pub struct Nexus<'n> {
@@ -448,6 +654,33 @@ pub struct Nexus<'n> {
     pause_waiters: Vec<oneshot::Sender<i32>>,
     /// information saved to a persistent store
     pub nexus_info: futures::lock::Mutex<NexusInfo>,
+    /// policy applied when too few children are healthy
+    replica_policy: AtomicCell<NexusReplicaPolicy>,
+    /// how unmaps are passed on to the children
+    trim_policy: AtomicCell<NexusTrimPolicy>,
+    /// trust the data of every child on creation, even of those which a
+    /// previous instance of the nexus had as unhealthy
+    assume_clean: AtomicCell<bool>,
+    /// when writes which failed on some children are acknowledged
+    write_ack_policy: AtomicCell<WriteAckPolicy>,
+    /// name of the child preferred for reads, if any
+    pub(crate) read_primary: parking_lot::Mutex<Option<String>>,
+    /// reconnect attempts of children which faulted, by child name
+    pub(crate) reconnects: parking_lot::Mutex<HashMap<String, ChildReconnect>>,
+    /// whether faulted children are replaced automatically
+    pub(crate) heal_policy: AtomicCell<NexusHealPolicy>,
+    /// time of the most recent automatic replacement of a child
+    pub(crate) last_heal: parking_lot::Mutex<Option<Instant>>,
+    /// outcomes of the most recent rebuilds, oldest first
+    pub(crate) rebuild_history: parking_lot::Mutex<VecDeque<RebuildRecord>>,
+    /// regions written while a child is not a writer, by child uuid, or
+    /// by child name for children without one
+    pub(crate) dirty_regions: parking_lot::Mutex<
+        HashMap<String, Arc<parking_lot::Mutex<RebuildMap>>>,
+    >,
+    /// set while any dirty regions are tracked, so that writes only take
+    /// the lock of the maps when they need to
+    pub(crate) dirty_tracking: AtomicCell<bool>,
     /// TODO
     event_sink: Option<DeviceEventSink>,
     /// Prevent auto-Unpin.
@@ -554,6 +787,17 @@ impl<'n> Nexus<'n> {
             pause_waiters: Vec::new(),
             nexus_info: futures::lock::Mutex::new(Default::default()),
             nexus_uuid: Default::default(),
+            replica_policy: AtomicCell::new(NexusReplicaPolicy::default()),
+            trim_policy: AtomicCell::new(NexusTrimPolicy::default()),
+            assume_clean: AtomicCell::new(false),
+            write_ack_policy: AtomicCell::new(WriteAckPolicy::default()),
+            read_primary: parking_lot::Mutex::new(None),
+            reconnects: parking_lot::Mutex::new(HashMap::new()),
+            heal_policy: AtomicCell::new(NexusHealPolicy::default()),
+            last_heal: parking_lot::Mutex::new(None),
+            rebuild_history: parking_lot::Mutex::new(VecDeque::new()),
+            dirty_regions: parking_lot::Mutex::new(HashMap::new()),
+            dirty_tracking: AtomicCell::new(false),
             event_sink: None,
             _pin: Default::default(),
         };
@@ -645,6 +889,68 @@ impl<'n> Nexus<'n> {
         state
     }
 
+    /// Returns the replica policy of the nexus.
+    pub fn replica_policy(&self) -> NexusReplicaPolicy {
+        self.replica_policy.load()
+    }
+
+    /// Sets the replica policy of the nexus. The policy is consulted for
+    /// every IO submitted from then on.
+    pub fn set_replica_policy(
+        &self,
+        policy: NexusReplicaPolicy,
+    ) -> Result<(), Error> {
+        policy.validate(&self.name, self.children.len())?;
+        info!("{}: setting replica policy {:?}", self.name, policy);
+        self.replica_policy.store(policy);
+        Ok(())
+    }
+
+    /// Returns true if enough children are open for the replica policy to
+    /// let the nexus serve IO, possibly read only. Otherwise the nexus is
+    /// faulted rather than degraded.
+    fn has_min_healthy(&self) -> bool {
+        let policy = self.replica_policy();
+        let open = self
+            .children
+            .iter()
+            .filter(|c| c.state() == ChildState::Open)
+            .count() as u32;
+        match policy.on_insufficient {
+            OnInsufficientReplicas::Fault => open >= policy.min_healthy,
+            OnInsufficientReplicas::ReadOnly => open > 0,
+        }
+    }
+
+    /// Returns the trim policy of the nexus.
+    pub fn trim_policy(&self) -> NexusTrimPolicy {
+        self.trim_policy.load()
+    }
+
+    /// Sets the trim policy of the nexus, which applies to every unmap
+    /// submitted from then on.
+    pub fn set_trim_policy(&self, policy: NexusTrimPolicy) {
+        info!("{}: setting trim policy {:?}", self.name, policy);
+        self.trim_policy.store(policy);
+    }
+
+    /// Returns the write ack policy of the nexus.
+    pub fn write_ack_policy(&self) -> WriteAckPolicy {
+        self.write_ack_policy.load()
+    }
+
+    /// Sets the write ack policy of the nexus, which applies to every write
+    /// completing from then on.
+    pub fn set_write_ack_policy(&self, policy: WriteAckPolicy) {
+        info!("{}: setting write ack policy {:?}", self.name, policy);
+        self.write_ack_policy.store(policy);
+    }
+
+    /// Returns the name of the child preferred for reads, if any.
+    pub fn read_primary(&self) -> Option<String> {
+        self.read_primary.lock().clone()
+    }
+
     /// Returns the actual size of the Nexus instance, in bytes.
     pub fn size_in_bytes(&self) -> u64 {
         self.bdev().size_in_bytes()
@@ -660,6 +966,112 @@ impl<'n> Nexus<'n> {
         self.bdev().num_blocks()
     }
 
+    /// Shrinks the nexus to `new_size` bytes. Unless forced, the tail which
+    /// is cut off is read back first and the shrink is refused if any of it
+    /// is not zero. The new size is persisted and the children which stay
+    /// larger than the nexus needs are returned, as they cannot be shrunk
+    /// while the nexus holds them open.
+    pub async fn shrink(
+        mut self: Pin<&mut Self>,
+        new_size: u64,
+        force: bool,
+    ) -> Result<Vec<UnreconciledChild>, Error> {
+        let block_len = self.block_len();
+        if new_size == 0
+            || new_size >= self.size_in_bytes()
+            || new_size % block_len != 0
+        {
+            return Err(Error::InvalidArguments {
+                name: self.name.clone(),
+                args: format!(
+                    "cannot shrink from {} to {} bytes, the new size must be smaller and a multiple of {}",
+                    self.size_in_bytes(),
+                    new_size,
+                    block_len
+                ),
+            });
+        }
+        if self.nexus_target.is_some() {
+            return Err(Error::InvalidArguments {
+                name: self.name.clone(),
+                args: "the nexus must be unshared before shrinking".to_string(),
+            });
+        }
+
+        if force {
+            warn!(
+                "{}: shrinking to {} bytes without checking for data",
+                self.name, new_size
+            );
+        } else {
+            self.check_tail_unused(new_size).await?;
+        }
+
+        info!(
+            "{}: shrinking from {} to {} bytes",
+            self.name,
+            self.size_in_bytes(),
+            new_size
+        );
+        let rc = unsafe {
+            spdk_bdev_notify_blockcnt_change(
+                self.bdev().as_ptr(),
+                new_size / block_len,
+            )
+        };
+        if rc != 0 {
+            return Err(Error::ShrinkNotify {
+                source: Errno::from_i32(rc.abs()),
+                name: self.name.clone(),
+            });
+        }
+        unsafe {
+            self.as_mut().get_unchecked_mut().req_size = new_size;
+        }
+        self.persist(PersistOp::Resize(new_size)).await;
+
+        let oversized = self.oversized_children(new_size);
+        for child in &oversized {
+            warn!(
+                "{}: child {} keeps its size: {}",
+                self.name, child.child, child.reason
+            );
+        }
+        Ok(oversized)
+    }
+
+    /// Reads the nexus from `offset` up to its end and fails if any of it
+    /// is not zero.
+    async fn check_tail_unused(&self, offset: u64) -> Result<(), Error> {
+        let scan_error = |reason: String| Error::ShrinkScan {
+            name: self.name.clone(),
+            reason,
+        };
+        let handle = BdevHandle::open_with_bdev(&self.bdev(), false)
+            .map_err(|e| scan_error(e.to_string()))?;
+
+        let size = self.size_in_bytes();
+        let mut pos = offset;
+        while pos < size {
+            let len = min(SHRINK_SCAN_SIZE, size - pos);
+            let mut buf = handle
+                .dma_malloc(len)
+                .map_err(|e| scan_error(e.to_string()))?;
+            handle
+                .read_at(pos, &mut buf)
+                .await
+                .map_err(|e| scan_error(e.to_string()))?;
+            if let Some(idx) = buf.as_slice().iter().position(|b| *b != 0) {
+                return Err(Error::ShrinkTailInUse {
+                    name: self.name.clone(),
+                    offset: pos + idx as u64,
+                });
+            }
+            pos += len;
+        }
+        Ok(())
+    }
+
     /// Reconfigures the child event handler.
     pub(crate) async fn reconfigure(&self, event: DrEvent) {
         info!(
@@ -700,6 +1112,22 @@ impl<'n> Nexus<'n> {
 
         debug!("Opening nexus {}", nex.name);
 
+        // what a previous instance of this nexus left behind, if anything
+        let previous = nex.load_persisted().await;
+
+        // a nexus which has been shrunk keeps its smaller size
+        let shrunk_size = previous
+            .as_ref()
+            .and_then(|info| info.size)
+            .filter(|size| *size < nex.req_size);
+        if let Some(size) = shrunk_size {
+            info!(
+                "{}: using the persisted size of {} bytes instead of {}",
+                nex.name, size, nex.req_size
+            );
+            unsafe { nex.as_mut().get_unchecked_mut().req_size = size };
+        }
+
         nex.as_mut().try_open_children().await?;
 
         // Register the bdev with SPDK and set the callbacks for io channel
@@ -717,8 +1145,21 @@ impl<'n> Nexus<'n> {
                 // We have to do this before setting the nexus to open so that
                 // nexus list does not return this nexus until it is persisted.
                 nex.persist(PersistOp::Create).await;
+                if let Some(size) = shrunk_size {
+                    nex.persist(PersistOp::Resize(size)).await;
+                }
+                if let Some(info) = &previous {
+                    nex.restore_dirty_regions(info);
+                }
                 nex.as_mut().set_state(NexusState::Open);
-                unsafe { nex.get_unchecked_mut().has_io_device = true };
+                unsafe {
+                    nex.as_mut().get_unchecked_mut().has_io_device = true
+                };
+                if let Some(info) = &previous {
+                    if !nex.assume_clean.load() {
+                        nex.as_mut().rebuild_stale_children(info).await;
+                    }
+                }
                 Ok(())
             }
             Err(err) => {
@@ -1068,15 +1509,10 @@ impl<'n> Nexus<'n> {
                     .all(|c| c.state() == ChildState::Open)
                 {
                     NexusStatus::Online
-                } else if self
-                    .children
-                    .iter()
-                    // at least one child online, so the Nexus is also online
-                    .any(|c| c.state() == ChildState::Open)
-                {
+                } else if self.has_min_healthy() {
                     NexusStatus::Degraded
                 } else {
-                    // nexus has no children or at least no child is online
+                    // too few children are online to serve IO
                     NexusStatus::Faulted
                 }
             }
@@ -1198,7 +1634,15 @@ impl<'n> BdevOps for Nexus<'n> {
             | IoType::Reset
             | IoType::Unmap
             | IoType::WriteZeros => {
-                let supported = self.io_is_supported(io_type);
+                let supported = match (io_type, self.trim_policy()) {
+                    (IoType::Unmap, NexusTrimPolicy::Drop) => true,
+                    (IoType::Unmap, NexusTrimPolicy::BestEffort) => self
+                        .children
+                        .iter()
+                        .filter_map(|c| c.get_device().ok())
+                        .any(|d| d.io_type_supported(io_type)),
+                    _ => self.io_is_supported(io_type),
+                };
                 if !supported {
                     trace!(
                         "IO type {:?} not supported for {}",
@@ -1254,11 +1698,94 @@ pub async fn nexus_create(
         uuid,
         None,
         NexusNvmeParams::default(),
+        NexusReplicaPolicy::default(),
+        false,
+        children,
+    )
+    .await
+}
+
+/// Create a new nexus like nexus_create() does, but trust the data of all
+/// of its children: none of them is rebuilt, even if a previous instance of
+/// the nexus had it as unhealthy. This is meant for importing children
+/// which are known to be in sync.
+pub async fn nexus_create_assume_clean(
+    name: &str,
+    size: u64,
+    uuid: Option<&str>,
+    children: &[String],
+) -> Result<(), Error> {
+    nexus_create_internal(
+        name,
+        size,
+        uuid,
+        None,
+        NexusNvmeParams::default(),
+        NexusReplicaPolicy::default(),
+        true,
         children,
     )
     .await
 }
 
+/// Create a new nexus like nexus_create() does, with the given replica
+/// policy applied from the start, so that the minimum number of healthy
+/// children is honoured as soon as the nexus is online.
+pub async fn nexus_create_with_replica_policy(
+    name: &str,
+    size: u64,
+    uuid: Option<&str>,
+    replica_policy: NexusReplicaPolicy,
+    children: &[String],
+) -> Result<(), Error> {
+    replica_policy.validate(name, children.len())?;
+    nexus_create_internal(
+        name,
+        size,
+        uuid,
+        None,
+        NexusNvmeParams::default(),
+        replica_policy,
+        false,
+        children,
+    )
+    .await
+}
+
+/// Checks that the NVMe controller ID range [min_cntlid, max_cntlid] is
+/// ordered, lies within the range allowed by the NVMe spec and leaves a
+/// controller ID for each of the `num_paths` paths.
+pub fn validate_cntlid_range(
+    name: &str,
+    min_cntlid: u16,
+    max_cntlid: u16,
+    num_paths: usize,
+) -> Result<(), Error> {
+    let args = if min_cntlid > max_cntlid {
+        format!(
+            "NVMe controller ID range [{:x}h, {:x}h] is inverted",
+            min_cntlid, max_cntlid
+        )
+    } else if min_cntlid < NVME_MIN_CNTLID || max_cntlid > NVME_MAX_CNTLID {
+        format!(
+            "NVMe controller ID range [{:x}h, {:x}h] is outside of [{:x}h, {:x}h]",
+            min_cntlid, max_cntlid, NVME_MIN_CNTLID, NVME_MAX_CNTLID
+        )
+    } else if ((max_cntlid - min_cntlid) as usize) < num_paths.saturating_sub(1)
+    {
+        format!(
+            "NVMe controller ID range [{:x}h, {:x}h] is too small for {} paths",
+            min_cntlid, max_cntlid, num_paths
+        )
+    } else {
+        return Ok(());
+    };
+    Err(Error::InvalidArguments {
+        name: name.to_owned(),
+        args,
+    })
+}
+
 /// As create_nexus with additional parameters:
 /// min_cntlid, max_cntldi: NVMe controller ID range when sharing over NVMf
 /// resv_key: NVMe reservation key for children
@@ -1269,20 +1796,16 @@ pub async fn nexus_create_v2(
     nvme_params: NexusNvmeParams,
     children: &[String],
 ) -> Result<(), Error> {
-    if nvme_params.min_cntlid < NVME_MIN_CNTLID
-        || nvme_params.min_cntlid > nvme_params.max_cntlid
-        || nvme_params.max_cntlid > NVME_MAX_CNTLID
-    {
-        let args = format!(
-            "invalid NVMe controller ID range [{:x}h, {:x}h]",
-            nvme_params.min_cntlid, nvme_params.max_cntlid
-        );
-        error!("failed to create nexus {}: {}", name, args);
-        return Err(Error::InvalidArguments {
-            name: name.to_owned(),
-            args,
-        });
-    }
+    validate_cntlid_range(
+        name,
+        nvme_params.min_cntlid,
+        nvme_params.max_cntlid,
+        1,
+    )
+    .map_err(|error| {
+        error!("failed to create nexus: {}", error);
+        error
+    })?;
     if nvme_params.resv_key == 0 {
         let args = "invalid NVMe reservation key";
         error!("failed to create nexus {}: {}", name, args);
@@ -1306,6 +1829,8 @@ pub async fn nexus_create_v2(
                 Some(bdev_uuid.as_str()),
                 Some(nexus_uuid),
                 nvme_params,
+                NexusReplicaPolicy::default(),
+                false,
                 children,
             )
             .await
@@ -1317,6 +1842,8 @@ pub async fn nexus_create_v2(
                 Some(uuid),
                 None,
                 nvme_params,
+                NexusReplicaPolicy::default(),
+                false,
                 children,
             )
             .await
@@ -1324,14 +1851,64 @@ pub async fn nexus_create_v2(
     }
 }
 
+/// Delay before retrying to create a child that is not present yet, doubled
+/// on every attempt up to CHILD_WAIT_MAX_DELAY.
+const CHILD_WAIT_MIN_DELAY: Duration = Duration::from_millis(100);
+const CHILD_WAIT_MAX_DELAY: Duration = Duration::from_secs(2);
+
+/// Create and register a child of a nexus being assembled, retrying until
+/// the deadline if the child device is not present yet, for example a
+/// remote target that is still starting up.
+/// `missing` lists the children still to be created, for logging.
+async fn create_child_wait(
+    mut nexus: Pin<&mut Nexus<'_>>,
+    child: &str,
+    missing: &[String],
+    deadline: Instant,
+) -> Result<(), NexusBdevError> {
+    let mut delay = CHILD_WAIT_MIN_DELAY;
+    loop {
+        let error = match nexus.as_mut().create_and_register(child).await {
+            Ok(()) => return Ok(()),
+            Err(error) => error,
+        };
+        if Instant::now() + delay > deadline {
+            return Err(error);
+        }
+        warn!(
+            "nexus {}: failed to create child {}, retrying in {:?}: {}; children still missing: {}",
+            nexus.name,
+            child,
+            delay,
+            error,
+            missing.join(", ")
+        );
+        if mayastor_sleep(delay).await.is_err() {
+            error!("Failed to wait for Mayastor sleep");
+        }
+        delay = std::cmp::min(delay * 2, CHILD_WAIT_MAX_DELAY);
+    }
+}
+
 async fn nexus_create_internal(
     name: &str,
     size: u64,
     bdev_uuid: Option<&str>,
     nexus_uuid: Option<Uuid>,
     nvme_params: NexusNvmeParams,
+    replica_policy: NexusReplicaPolicy,
+    assume_clean: bool,
     children: &[String],
 ) -> Result<(), Error> {
+    if children.is_empty() {
+        let args = "a nexus needs at least one child";
+        error!("failed to create nexus {}: {}", name, args);
+        return Err(Error::InvalidArguments {
+            name: name.to_owned(),
+            args: args.to_string(),
+        });
+    }
+
     if let Some(nexus) = nexus_lookup_name_uuid(name, nexus_uuid) {
         // FIXME: Instead of error, we return Ok without checking
         // that the children match, which seems wrong.
@@ -1358,10 +1935,19 @@ async fn nexus_create_internal(
     // nexus instance gets removed from the global list if an error occurs.
     let mut nexus_bdev =
         Nexus::new(name, size, bdev_uuid, nexus_uuid, nvme_params, None);
-
-    for child in children {
-        if let Err(error) =
-            nexus_bdev.data_mut().create_and_register(child).await
+    nexus_bdev.data().replica_policy.store(replica_policy);
+    nexus_bdev.data().assume_clean.store(assume_clean);
+
+    let deadline = Instant::now()
+        + Duration::from_millis(Config::get().nexus_opts.child_wait_timeout_ms);
+    for (idx, child) in children.iter().enumerate() {
+        if let Err(error) = create_child_wait(
+            nexus_bdev.data_mut(),
+            child,
+            &children[idx ..],
+            deadline,
+        )
+        .await
         {
             error!(
                 "failed to create nexus {}: failed to create child {}: {}",
@@ -23,7 +23,10 @@
 //! When reconfiguring the nexus, we traverse all our children, create new IO
 //! channels for all children that are in the open state.
 
-use std::{cmp::min, pin::Pin};
+use std::{
+    cmp::{max, min},
+    pin::Pin,
+};
 
 use futures::future::join_all;
 use snafu::ResultExt;
@@ -31,6 +34,7 @@ use snafu::ResultExt;
 use super::{
     fault_nexus_child,
     nexus_iter_mut,
+    ChildResvPreemptFailed,
     ChildState,
     CreateChild,
     DrEvent,
@@ -51,7 +55,7 @@ use crate::{
         device_lookup,
         nexus::nexus_persistence::PersistOp,
     },
-    core::{partition, DeviceEventListener, DeviceEventType, Reactors},
+    core::{partition, DeviceEventListener, DeviceEventType, IoType, Reactors},
     nexus_uri::NexusBdevError,
 };
 
@@ -127,7 +131,7 @@ impl<'n> Nexus<'n> {
         let status = self.as_mut().add_child_only(uri).await?;
 
         if !norebuild {
-            if let Err(e) = self.as_mut().start_rebuild(uri).await {
+            if let Err(e) = self.as_mut().start_or_queue_rebuild(uri).await {
                 // todo: CAS-253 retry starting the rebuild again when ready
                 error!(
                     "Child added but rebuild failed to start: {}",
@@ -237,6 +241,7 @@ impl<'n> Nexus<'n> {
                     self.as_mut().get_unchecked_mut().children.push(child);
                     self.as_mut().get_unchecked_mut().child_count += 1;
                 }
+                self.as_mut().update_max_io_size();
 
                 self.persist(PersistOp::AddChild((cn, child_state))).await;
 
@@ -317,6 +322,8 @@ impl<'n> Nexus<'n> {
             self.as_mut().get_unchecked_mut().children.remove(idx);
             self.as_mut().get_unchecked_mut().child_count -= 1;
         }
+        self.stop_dirty_tracking(uri);
+        self.as_mut().update_max_io_size();
 
         self.persist(PersistOp::Update((uri.to_string(), child_state)))
             .await;
@@ -335,6 +342,9 @@ impl<'n> Nexus<'n> {
         let cancelled_rebuilding_children =
             self.cancel_child_rebuild_jobs(name).await;
 
+        // writes the child misses from now on are rebuilt when it is back
+        self.start_dirty_tracking(name);
+
         unsafe {
             if let Some(child) = self
                 .as_mut()
@@ -448,7 +458,7 @@ impl<'n> Nexus<'n> {
                     child: name.to_owned(),
                     name: nexus_name,
                 })?;
-                self.as_mut().start_rebuild(name).await.map(|_| {})?;
+                self.as_mut().start_or_queue_rebuild(name).await?;
                 Ok(self.status())
             } else {
                 Err(Error::ChildNotFound {
@@ -545,6 +555,10 @@ impl<'n> Nexus<'n> {
             self.as_mut().get_unchecked_mut().data_ent_offset = start_blk;
             self.as_mut().bdev_mut().set_block_len(blk_size as u32);
             self.as_mut().bdev_mut().set_num_blocks(end_blk - start_blk);
+            // the children may cache writes, so let the initiator know that
+            // flushes are needed to make writes durable
+            let write_cache = self.io_is_supported(IoType::Flush);
+            self.as_mut().bdev_mut().set_write_cache(write_cache);
         }
 
         let size = self.req_size;
@@ -661,6 +675,7 @@ impl<'n> Nexus<'n> {
                 }
             }
         }
+        self.as_mut().update_max_io_size();
 
         Ok(())
     }
@@ -681,6 +696,123 @@ impl<'n> Nexus<'n> {
         }
     }
 
+    /// Preempt the NVMe reservation that another host holds with
+    /// `victim_key` on all open children, taking over the reservation with
+    /// `our_key` and the given reservation type. This fences off the other
+    /// host, which must no longer write to the children.
+    pub async fn resv_preempt(
+        &self,
+        our_key: u64,
+        victim_key: u64,
+        resv_type: u8,
+    ) -> Result<(), Error> {
+        let args = if our_key == 0 || victim_key == 0 {
+            Some("reservation keys must not be 0".to_string())
+        } else if our_key == victim_key {
+            Some("cannot preempt our own reservation key".to_string())
+        } else if !(1 ..= 6).contains(&resv_type) {
+            // reservation types defined by the NVMe spec
+            Some(format!("invalid reservation type {:x}h", resv_type))
+        } else {
+            None
+        };
+        if let Some(args) = args {
+            return Err(Error::InvalidArguments {
+                name: self.name.clone(),
+                args,
+            });
+        }
+
+        for child in self
+            .children
+            .iter()
+            .filter(|c| c.state() == ChildState::Open)
+        {
+            child
+                .resv_preempt(our_key, victim_key, resv_type)
+                .await
+                .context(ChildResvPreemptFailed {
+                    preempt_key: victim_key,
+                    child: child.get_name().to_string(),
+                    name: self.name.clone(),
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Prefer the given child for all reads, e.g. the replica local to the
+    /// application. The other children only serve reads while the primary
+    /// is not part of the IO path.
+    pub async fn promote_read_primary(&self, name: &str) -> Result<(), Error> {
+        let child = self
+            .children
+            .iter()
+            .find(|c| c.get_name() == name)
+            .ok_or_else(|| Error::ChildNotFound {
+                child: name.to_owned(),
+                name: self.name.clone(),
+            })?;
+        if child.state() != ChildState::Open {
+            return Err(Error::ReadPrimaryNotOpen {
+                child: name.to_owned(),
+                name: self.name.clone(),
+                state: child.state().to_string(),
+            });
+        }
+
+        info!("{}: promoting {} to read primary", self.name, name);
+        *self.read_primary.lock() = Some(name.to_owned());
+        self.reconfigure(DrEvent::ReadPrimary).await;
+        Ok(())
+    }
+
+    /// Stop preferring the given child for reads, spreading them over all
+    /// healthy children again. Does nothing if it is not the read primary.
+    pub async fn demote_read_primary(&self, name: &str) -> Result<(), Error> {
+        if !self.children.iter().any(|c| c.get_name() == name) {
+            return Err(Error::ChildNotFound {
+                child: name.to_owned(),
+                name: self.name.clone(),
+            });
+        }
+        if self.read_primary().as_deref() != Some(name) {
+            return Ok(());
+        }
+
+        info!("{}: demoting {} from read primary", self.name, name);
+        *self.read_primary.lock() = None;
+        self.reconfigure(DrEvent::ReadPrimary).await;
+        Ok(())
+    }
+
+    /// Returns the largest IO in bytes that all children accept, if any of
+    /// them limits it.
+    pub fn max_io_size(&self) -> Option<u64> {
+        self.children
+            .iter()
+            .filter_map(|c| c.get_device().ok()?.max_io_size())
+            .min()
+    }
+
+    /// Have the bdev layer split IOs to the nexus which are larger than
+    /// what its children accept, as they would fail otherwise. Called
+    /// whenever the children change, so that IOs are no longer split once
+    /// no child limits them.
+    fn update_max_io_size(self: Pin<&mut Self>) {
+        let max_io_size = self.max_io_size();
+        let boundary = max_io_size
+            .map(|size| max(size / self.bdev().block_len() as u64, 1) as u32);
+        match max_io_size {
+            Some(size) => {
+                info!("{}: splitting IOs larger than {} bytes", self.name, size)
+            }
+            None => debug!("{}: not splitting IOs", self.name),
+        }
+        unsafe {
+            self.bdev_mut().set_optimal_io_boundary(boundary);
+        }
+    }
+
     /// The nexus is allowed to be smaller then the underlying child devices
     /// this function returns the smallest blkcnt of all online children as
     /// they MAY vary in size.
@@ -1,6 +1,16 @@
 use futures::channel::oneshot::Receiver;
+use once_cell::sync::Lazy;
+use serde::Serialize;
 use snafu::ResultExt;
-use std::pin::Pin;
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+        Mutex,
+    },
+};
 
 use rpc::mayastor::{
     RebuildProgressReply,
@@ -9,12 +19,16 @@ use rpc::mayastor::{
 };
 
 use super::{
+    nexus_iter,
     nexus_lookup_mut,
     ChildState,
     CreateRebuild,
     DrEvent,
     Error,
     Nexus,
+    NexusChild,
+    NexusInfo,
+    NexusState,
     Reason,
     RebuildJobNotFound,
     RebuildOperation,
@@ -29,12 +43,104 @@ use crate::{
         ClientOperations,
         RebuildError,
         RebuildJob,
+        RebuildMap,
         RebuildState,
         RebuildStats,
     },
 };
 
+/// maximum number of rebuild jobs running at once, 0 meaning no limit
+static MAX_REBUILDS: AtomicUsize = AtomicUsize::new(0);
+
+/// rebuilds waiting for a free slot, as (nexus, child) pairs
+static PENDING_REBUILDS: Lazy<Mutex<VecDeque<(String, String)>>> =
+    Lazy::new(|| Mutex::new(VecDeque::new()));
+
+/// number of finished rebuilds remembered per nexus
+const REBUILD_HISTORY_LEN: usize = 32;
+
+/// Outcome of a finished rebuild of one of the nexus children.
+#[derive(Debug, Clone, Serialize)]
+pub struct RebuildRecord {
+    /// child which was rebuilt
+    pub child: String,
+    /// child the data was copied from
+    pub source: String,
+    /// final state of the rebuild job
+    pub state: String,
+    /// error which made the rebuild fail, if any
+    pub error: Option<String>,
+    /// number of bytes copied
+    pub bytes_recovered: u64,
+    /// time at which the rebuild started in RFC 3339 format
+    pub start_time: String,
+    /// how long the rebuild ran for in milliseconds
+    pub duration_ms: i64,
+}
+
+impl From<&RebuildJob> for RebuildRecord {
+    fn from(job: &RebuildJob) -> Self {
+        let stats = job.stats();
+        RebuildRecord {
+            child: job.destination.clone(),
+            source: job.source.clone(),
+            state: job.state().to_string(),
+            error: job.error.as_ref().map(|_| job.error_desc()),
+            bytes_recovered: stats.blocks_recovered * stats.block_size,
+            start_time: job.start_time.to_rfc3339(),
+            duration_ms: (chrono::Utc::now() - job.start_time)
+                .num_milliseconds(),
+        }
+    }
+}
+
+/// A running rebuild of one of the nexus children.
+#[derive(Debug, Clone, Serialize)]
+pub struct ActiveRebuild {
+    /// nexus the child belongs to
+    pub nexus: String,
+    /// child being rebuilt
+    pub child: String,
+    /// child the data is copied from
+    pub source: String,
+    /// current state of the rebuild job
+    pub state: String,
+    /// rebuild progress in %
+    pub progress: u64,
+}
+
+impl From<&RebuildJob> for ActiveRebuild {
+    fn from(job: &RebuildJob) -> Self {
+        ActiveRebuild {
+            nexus: job.nexus.clone(),
+            child: job.destination.clone(),
+            source: job.source.clone(),
+            state: job.state().to_string(),
+            progress: job.stats().progress,
+        }
+    }
+}
+
+/// Returns the rebuild limit if it has been reached.
+fn rebuild_limit_reached() -> Option<usize> {
+    let limit = MAX_REBUILDS.load(Ordering::Relaxed);
+    if limit != 0 && RebuildJob::count() >= limit {
+        Some(limit)
+    } else {
+        None
+    }
+}
+
 impl<'n> Nexus<'n> {
+    /// Sets the maximum number of rebuild jobs which may run at once across
+    /// all nexuses, 0 meaning no limit. Raising the limit starts queued
+    /// rebuilds straight away.
+    pub async fn set_max_rebuilds(max: usize) {
+        info!("setting the maximum number of rebuilds to {}", max);
+        MAX_REBUILDS.store(max, Ordering::Relaxed);
+        Self::start_pending_rebuilds().await;
+    }
+
     /// Starts a rebuild job and returns a receiver channel
     /// which can be used to await the rebuild completion
     pub async fn start_rebuild(
@@ -54,6 +160,99 @@ impl<'n> Nexus<'n> {
             }),
         }?;
 
+        self.start_rebuild_from(&src_child_name, name).await
+    }
+
+    /// Starts a rebuild job of child `name` which copies from the given
+    /// source child rather than from one picked by the nexus; the source
+    /// must be healthy
+    pub async fn rebuild_with_source(
+        self: Pin<&mut Self>,
+        name: &str,
+        source: &str,
+    ) -> Result<Receiver<RebuildState>, Error> {
+        trace!(
+            "{}: rebuild request for {} from source {}",
+            self.name,
+            name,
+            source
+        );
+
+        if source == name {
+            return Err(Error::InvalidArguments {
+                name: self.name.clone(),
+                args: format!("child {} cannot rebuild from itself", name),
+            });
+        }
+
+        match self.children.iter().find(|c| c.get_name() == source) {
+            Some(c) if c.state() == ChildState::Open => Ok(()),
+            Some(c) => Err(Error::InvalidRebuildSource {
+                child: source.to_owned(),
+                name: self.name.clone(),
+                state: c.state().to_string(),
+            }),
+            None => Err(Error::ChildNotFound {
+                child: source.to_owned(),
+                name: self.name.clone(),
+            }),
+        }?;
+
+        self.start_rebuild_from(source, name).await
+    }
+
+    /// Checks that a child is reachable and writable, so that a rebuild onto
+    /// a child which is registered but not healthy fails straight away.
+    /// The probe writes to the last block of the metadata reservation which
+    /// precedes the data partition, so it never touches data written to the
+    /// nexus.
+    pub async fn probe_child(&self, name: &str) -> Result<(), Error> {
+        let child = self
+            .children
+            .iter()
+            .find(|c| c.get_name() == name)
+            .ok_or_else(|| Error::ChildNotFound {
+                child: name.to_owned(),
+                name: self.name.clone(),
+            })?;
+        // the reservation is only laid out once the nexus is open
+        let open = matches!(
+            *self.state.lock(),
+            NexusState::Open | NexusState::Reconfiguring
+        );
+        if !open || self.data_ent_offset == 0 {
+            return Err(Error::ChildProbeUnavailable {
+                child: name.to_owned(),
+                name: self.name.clone(),
+            });
+        }
+        let offset =
+            (self.data_ent_offset - 1) * u64::from(self.bdev().block_len());
+        child.probe(offset).await.map_err(|source| {
+            error!("{}: probe of child {} failed: {}", self.name, name, source);
+            Error::ChildProbe {
+                source,
+                child: name.to_owned(),
+                name: self.name.clone(),
+            }
+        })
+    }
+
+    /// Creates and starts the rebuild job of child `name` from the source
+    /// child `src_child_name`
+    async fn start_rebuild_from(
+        self: Pin<&mut Self>,
+        src_child_name: &str,
+        name: &str,
+    ) -> Result<Receiver<RebuildState>, Error> {
+        if let Some(limit) = rebuild_limit_reached() {
+            return Err(Error::RebuildLimitReached {
+                child: name.to_owned(),
+                name: self.name.clone(),
+                limit,
+            });
+        }
+
         let dst_child_name =
             match self.children.iter().find(|c| c.get_name() == name) {
                 Some(c)
@@ -72,14 +271,29 @@ impl<'n> Nexus<'n> {
                 }),
             }?;
 
+        self.probe_child(&dst_child_name).await?;
+
+        // only the regions written while the child was away, if tracked; the
+        // map keeps being marked until the child receives all writes below
+        let map = self.dirty_map(&dst_child_name);
+        if let Some(map) = &map {
+            info!(
+                "{}: rebuilding {} dirty regions of child {}",
+                self.name,
+                map.lock().count_dirty(),
+                name
+            );
+        }
+
         let job = RebuildJob::create(
             &self.name,
-            &src_child_name,
+            src_child_name,
             &dst_child_name,
             std::ops::Range::<u64> {
                 start: self.data_ent_offset,
                 end: self.bdev().num_blocks() + self.data_ent_offset,
             },
+            map,
             |nexus, job| {
                 Reactors::current().send_future(async move {
                     Nexus::notify_rebuild(nexus, job).await;
@@ -100,6 +314,7 @@ impl<'n> Nexus<'n> {
         // Ensuring that the dst child receives all frontend Write IO keeps all
         // rebuilt ranges in sync with the other children.
         self.reconfigure(DrEvent::ChildRebuild).await;
+        self.stop_dirty_tracking(&dst_child_name);
 
         job.as_client().start().context(RebuildOperation {
             job: name.to_owned(),
@@ -219,6 +434,166 @@ impl<'n> Nexus<'n> {
         rebuilding_children
     }
 
+    /// Returns the running rebuilds of the children of the nexus.
+    pub fn rebuilds(&self) -> Vec<ActiveRebuild> {
+        self.children
+            .iter()
+            .filter_map(|c| self.get_rebuild_job(&c.name).ok())
+            .map(|job| ActiveRebuild::from(&*job))
+            .collect()
+    }
+
+    /// Returns the running rebuilds of all nexuses.
+    pub fn list_rebuilds() -> Vec<ActiveRebuild> {
+        nexus_iter().flat_map(|n| n.rebuilds()).collect()
+    }
+
+    /// Cancels the rebuild of the child, whether running or queued. The
+    /// child is left out of sync, so that its rebuild can be started again
+    /// later on.
+    pub fn cancel_rebuild(&self, name: &str) -> Result<(), Error> {
+        let entry = (self.name.clone(), name.to_owned());
+        let queued = {
+            let mut pending = PENDING_REBUILDS.lock().unwrap();
+            let len = pending.len();
+            pending.retain(|p| p != &entry);
+            pending.len() != len
+        };
+
+        match self.get_rebuild_job(name) {
+            Ok(rj) => {
+                info!("{}: cancelling rebuild of child {}", self.name, name);
+                rj.as_client().stop().context(RebuildOperation {
+                    job: name.to_owned(),
+                    name: self.name.clone(),
+                })
+            }
+            Err(_) if queued => {
+                info!("{}: dropped queued rebuild of {}", self.name, name);
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Cancels the rebuilds of all nexuses, dropping the queued ones first
+    /// so that none starts in place of the cancelled ones. Returns the
+    /// rebuilds which were running.
+    pub fn cancel_all_rebuilds() -> Vec<ActiveRebuild> {
+        let dropped = std::mem::take(&mut *PENDING_REBUILDS.lock().unwrap());
+        if !dropped.is_empty() {
+            warn!("Dropped {} queued rebuilds", dropped.len());
+        }
+
+        let rebuilds = Self::list_rebuilds();
+        for rebuild in &rebuilds {
+            if let Some(nexus) = nexus_lookup_mut(&rebuild.nexus) {
+                if let Err(e) = nexus.cancel_rebuild(&rebuild.child) {
+                    error!(
+                        "Failed to cancel rebuild of {}: {}",
+                        rebuild.child,
+                        e.verbose()
+                    );
+                }
+            }
+        }
+        rebuilds
+    }
+
+    /// Starts a rebuild of the child or, when too many rebuilds are already
+    /// running, queues it until one of them completes
+    pub async fn start_or_queue_rebuild(
+        mut self: Pin<&mut Self>,
+        name: &str,
+    ) -> Result<(), Error> {
+        match self.as_mut().start_rebuild(name).await {
+            Err(Error::RebuildLimitReached {
+                ..
+            }) => {
+                info!("{}: queueing rebuild of child {}", self.name, name);
+                let mut pending = PENDING_REBUILDS.lock().unwrap();
+                let entry = (self.name.clone(), name.to_owned());
+                if !pending.contains(&entry) {
+                    pending.push_back(entry);
+                }
+                Ok(())
+            }
+            result => result.map(|_| ()),
+        }
+    }
+
+    /// Rebuilds the children which the previous instance of the nexus had
+    /// as unhealthy, as their data cannot be trusted. A child which is the
+    /// last healthy one is left as it is.
+    pub(crate) async fn rebuild_stale_children(
+        mut self: Pin<&mut Self>,
+        info: &NexusInfo,
+    ) {
+        let stale = self
+            .children
+            .iter()
+            .filter(|child| {
+                NexusChild::uuid(&child.name).map_or(false, |uuid| {
+                    info.children.iter().any(|c| !c.healthy && c.uuid == uuid)
+                })
+            })
+            .map(|child| child.name.clone())
+            .collect::<Vec<_>>();
+
+        for child in stale {
+            warn!(
+                "{}: child {} was unhealthy in the previous instance of the nexus, rebuilding it",
+                self.name, child
+            );
+            if let Err(e) =
+                self.as_mut().fault_child(&child, Reason::OutOfSync).await
+            {
+                error!(
+                    "{}: failed to fault stale child {}: {}",
+                    self.name,
+                    child,
+                    e.verbose()
+                );
+                continue;
+            }
+            if let Err(e) = self.as_mut().start_or_queue_rebuild(&child).await {
+                error!(
+                    "{}: failed to start the rebuild of stale child {}: {}",
+                    self.name,
+                    child,
+                    e.verbose()
+                );
+            }
+        }
+    }
+
+    /// Starts queued rebuilds for as long as the rebuild limit allows it.
+    /// Queued rebuilds which can no longer start, e.g. because the child
+    /// has been removed, are dropped.
+    async fn start_pending_rebuilds() {
+        while rebuild_limit_reached().is_none() {
+            let next = PENDING_REBUILDS.lock().unwrap().pop_front();
+            let (nexus, child) = match next {
+                Some(next) => next,
+                None => break,
+            };
+            match nexus_lookup_mut(&nexus) {
+                Some(nexus) => {
+                    if let Err(e) = nexus.start_rebuild(&child).await {
+                        error!(
+                            "Failed to start queued rebuild: {}",
+                            e.verbose()
+                        );
+                    }
+                }
+                None => warn!(
+                    "Dropping queued rebuild of {}: nexus {} not found",
+                    child, nexus
+                ),
+            }
+        }
+    }
+
     /// Start a rebuild for each of the children
     /// todo: how to proceed if no healthy child is found?
     pub async fn start_rebuild_jobs(
@@ -226,7 +601,7 @@ impl<'n> Nexus<'n> {
         child_names: Vec<String>,
     ) {
         for name in child_names {
-            if let Err(e) = self.as_mut().start_rebuild(&name).await {
+            if let Err(e) = self.as_mut().start_or_queue_rebuild(&name).await {
                 error!("Failed to start rebuild: {}", e.verbose());
             }
         }
@@ -259,12 +634,158 @@ impl<'n> Nexus<'n> {
         Ok(job)
     }
 
+    /// Returns the key under which the dirty regions of a child are kept:
+    /// its uuid, which stays the same when it is added back after a restart,
+    /// or its name when it has none.
+    fn dirty_key(child: &str) -> String {
+        NexusChild::uuid(child).unwrap_or_else(|| child.to_string())
+    }
+
+    /// Starts recording the regions written to the nexus for a child which
+    /// stops receiving writes. Only a child which is in sync gets a map of
+    /// its own; any other child is rebuilt in full.
+    pub(crate) fn start_dirty_tracking(&self, child: &str) {
+        let mut dirty = self.dirty_regions.lock();
+        let key = Self::dirty_key(child);
+        if dirty.contains_key(&key) {
+            return;
+        }
+        if self
+            .children
+            .iter()
+            .any(|c| c.name == child && c.state() == ChildState::Open)
+        {
+            info!("{}: tracking dirty regions of {}", self.name, child);
+            dirty.insert(
+                key,
+                Arc::new(parking_lot::Mutex::new(RebuildMap::new(
+                    self.num_blocks(),
+                    self.block_len(),
+                ))),
+            );
+            self.dirty_tracking.store(true);
+        }
+    }
+
+    /// Stops recording the regions written to the nexus for a child, once
+    /// it receives all writes again or is gone.
+    pub(crate) fn stop_dirty_tracking(&self, child: &str) {
+        let mut dirty = self.dirty_regions.lock();
+        if dirty.remove(&Self::dirty_key(child)).is_some() {
+            info!(
+                "{}: no longer tracking dirty regions of {}",
+                self.name, child
+            );
+        }
+        self.dirty_tracking.store(!dirty.is_empty());
+    }
+
+    /// Records a write to the given block range of the nexus in the map of
+    /// every child which is being tracked
+    pub(crate) fn mark_dirty(&self, offset: u64, num_blocks: u64) {
+        if !self.dirty_tracking.load() {
+            return;
+        }
+        for map in self.dirty_regions.lock().values() {
+            map.lock().mark(offset, num_blocks);
+        }
+    }
+
+    /// Returns the regions written since the child stopped receiving writes,
+    /// if they have been tracked from then on
+    fn dirty_map(
+        &self,
+        child: &str,
+    ) -> Option<Arc<parking_lot::Mutex<RebuildMap>>> {
+        self.dirty_regions
+            .lock()
+            .get(&Self::dirty_key(child))
+            .cloned()
+    }
+
+    /// Returns a copy of the tracked dirty regions, by child uuid
+    pub(crate) fn dirty_regions_snapshot(&self) -> Vec<(String, RebuildMap)> {
+        self.dirty_regions
+            .lock()
+            .iter()
+            .map(|(key, map)| (key.clone(), map.lock().clone()))
+            .collect()
+    }
+
+    /// Picks up the dirty regions saved by the previous instance of the
+    /// nexus for the children it does not have, so that they are caught up
+    /// from those regions once they are added back. They are only trusted
+    /// when that instance was destroyed cleanly, otherwise writes may have
+    /// been missed and such children are rebuilt in full.
+    pub(crate) fn restore_dirty_regions(&self, info: &NexusInfo) {
+        let saved = info
+            .children
+            .iter()
+            .filter(|c| {
+                !self
+                    .children
+                    .iter()
+                    .any(|child| Self::dirty_key(&child.name) == c.uuid)
+            })
+            .filter_map(|c| c.dirty_regions.as_ref().map(|map| (c, map)))
+            .collect::<Vec<_>>();
+        if saved.is_empty() {
+            return;
+        }
+        if !info.clean_shutdown {
+            warn!(
+                "{}: not restoring the dirty regions of {} children as the nexus did not shut down cleanly",
+                self.name,
+                saved.len()
+            );
+            return;
+        }
+
+        let mut dirty = self.dirty_regions.lock();
+        for (child, map) in saved {
+            if !map.covers(self.num_blocks(), self.block_len()) {
+                warn!(
+                    "{}: dirty regions of child {} do not match the nexus size, ignoring them",
+                    self.name, child.uuid
+                );
+                continue;
+            }
+            info!(
+                "{}: restored {} dirty regions of child {}",
+                self.name,
+                map.count_dirty(),
+                child.uuid
+            );
+            dirty.insert(
+                child.uuid.clone(),
+                Arc::new(parking_lot::Mutex::new(map.clone())),
+            );
+        }
+        self.dirty_tracking.store(!dirty.is_empty());
+    }
+
+    /// Returns the outcomes of the most recent rebuilds, oldest first
+    pub fn rebuild_history(&self) -> Vec<RebuildRecord> {
+        self.rebuild_history.lock().iter().cloned().collect()
+    }
+
+    /// Records the outcome of a finished rebuild job
+    fn record_rebuild(&self, job: &RebuildJob) {
+        let mut history = self.rebuild_history.lock();
+        if history.len() == REBUILD_HISTORY_LEN {
+            history.pop_front();
+        }
+        history.push_back(job.into());
+    }
+
     /// On rebuild job completion it updates the child and the nexus
     /// based on the rebuild job's final state
     async fn on_rebuild_complete_job(
         mut self: Pin<&mut Self>,
         job: &RebuildJob,
     ) -> Result<(), Error> {
+        self.record_rebuild(job);
+
         let recovering_child =
             self.as_mut().get_child_by_name(&job.destination)?;
 
@@ -357,6 +878,9 @@ impl<'n> Nexus<'n> {
         } else {
             error!("Failed to find nexus {} for rebuild job {}", nexus, job);
         }
+
+        // a finished job frees up a slot for queued rebuilds
+        Nexus::start_pending_rebuilds().await;
     }
 }
 
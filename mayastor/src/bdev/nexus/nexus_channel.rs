@@ -18,6 +18,8 @@ pub(crate) struct NexusChannelInner {
     pub(crate) writers: Vec<Box<dyn BlockDeviceHandle>>,
     pub(crate) readers: Vec<Box<dyn BlockDeviceHandle>>,
     pub(crate) previous: usize,
+    /// index of the reader of the read primary child, if any
+    pub(crate) primary: Option<usize>,
     pub(crate) fail_fast: u32,
     nexus_ref: *mut c_void,
 }
@@ -45,6 +47,8 @@ pub enum DrEvent {
     ChildRemove,
     /// Child rebuild event
     ChildRebuild,
+    /// Read primary change event
+    ReadPrimary,
 }
 
 /// Mark nexus child as faulted based on its device name
@@ -96,6 +100,9 @@ impl NexusChannelInner {
     /// threads more often depending on what core we are on etc, we might be
     /// "awaiting' while the thread is already trying to submit IO.
     pub(crate) fn child_select(&mut self) -> Option<usize> {
+        if self.primary.is_some() {
+            return self.primary;
+        }
         if self.readers.is_empty() {
             None
         } else {
@@ -108,6 +115,25 @@ impl NexusChannelInner {
         }
     }
 
+    /// Locate the reader of the read primary child of the nexus, so that it
+    /// is preferred for all reads as long as it is part of this channel.
+    fn update_primary(&mut self) {
+        let nexus = self.get_nexus();
+        let device = nexus
+            .read_primary()
+            .and_then(|name| {
+                nexus.children.iter().find(|c| c.get_name() == name)
+            })
+            .and_then(|c| c.get_device().ok())
+            .map(|d| d.device_name());
+        let primary = device.and_then(|device| {
+            self.readers
+                .iter()
+                .position(|r| r.get_device().device_name() == device)
+        });
+        self.primary = primary;
+    }
+
     /// Remove a child from the readers and/or writers
     pub fn remove_child(&mut self, name: &str) -> bool {
         self.previous = 0;
@@ -127,6 +153,7 @@ impl NexusChannelInner {
             .retain(|c| c.get_device().device_name() != name);
         self.writers
             .retain(|c| c.get_device().device_name() != name);
+        self.update_primary();
 
         trace!(?name,
             "core: {} thread: {}: New number of IO channels write:{} read:{} out of {} children",
@@ -221,6 +248,7 @@ impl NexusChannelInner {
 
         self.writers = writers;
         self.readers = readers;
+        self.update_primary();
 
         trace!(
             "{}: New number of IO channels write:{} read:{} out of {} children",
@@ -257,14 +285,16 @@ impl NexusChannel {
                 });
         }
 
-        let channels = Box::new(NexusChannelInner {
+        let mut channels = Box::new(NexusChannelInner {
             writers,
             readers,
             previous: 0,
+            primary: None,
             nexus_ref: unsafe { &mut *Pin::get_unchecked_mut(nexus) }
                 as *mut Nexus as *mut c_void,
             fail_fast: 0,
         });
+        channels.update_primary();
 
         Self {
             inner: Box::into_raw(channels),
@@ -1,4 +1,5 @@
 use std::{
+    collections::VecDeque,
     fmt::{Debug, Display, Formatter},
     marker::PhantomData,
 };
@@ -6,11 +7,20 @@ use std::{
 use crossbeam::atomic::AtomicCell;
 use futures::{channel::mpsc, SinkExt, StreamExt};
 use nix::errno::Errno;
+use parking_lot::Mutex;
 use serde::Serialize;
 use snafu::{ResultExt, Snafu};
 use url::Url;
 
-use super::{nexus_iter_mut, nexus_lookup_mut, DrEvent, VerboseError};
+use super::{
+    nexus_iter_mut,
+    nexus_lookup_mut,
+    ChildHealth,
+    DrEvent,
+    VerboseError,
+    HEALTH_LOG_PAGE,
+    HEALTH_LOG_PAGE_SIZE,
+};
 
 use crate::{
     bdev::{device_create, device_destroy, device_lookup},
@@ -37,6 +47,7 @@ use spdk_rs::{
     nvme_reservation_register_action,
     nvme_reservation_register_cptpl,
     nvme_reservation_type,
+    DmaBuf,
     DmaError,
 };
 
@@ -83,6 +94,14 @@ pub enum ChildError {
     ResvReport { source: CoreError },
     #[snafu(display("Failed to get NVMe host ID: {}", source))]
     NvmeHostId { source: CoreError },
+    #[snafu(display("Failed to read health log page: {}", source))]
+    HealthLogPage { source: CoreError },
+    #[snafu(display("Failed to read from child: {}", source))]
+    ProbeRead { source: CoreError },
+    #[snafu(display("Failed to write to child: {}", source))]
+    ProbeWrite { source: CoreError },
+    #[snafu(display("Child returned different data than written"))]
+    ProbeMismatch {},
     #[snafu(display("Failed to create a BlockDevice for child {}", child))]
     ChildBdevCreate {
         child: String,
@@ -127,6 +146,22 @@ impl Display for Reason {
     }
 }
 
+/// number of state transitions remembered per child
+const STATE_HISTORY_LEN: usize = 16;
+
+/// A recorded change of a child's state.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChildStateTransition {
+    /// state before the transition
+    pub from: ChildState,
+    /// state after the transition
+    pub to: ChildState,
+    /// what triggered the transition, when known
+    pub cause: Option<String>,
+    /// time of the transition in RFC 3339 format
+    pub timestamp: String,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
 pub enum ChildState {
     /// child has not been opened, but we are in the process of opening it
@@ -140,6 +175,9 @@ pub enum ChildState {
     Destroying,
     /// the child has been closed by the nexus
     Closed,
+    /// the child has been taken offline on request and is skipped by IO
+    /// until it is brought back online
+    Offline,
     /// the child is faulted
     Faulted(Reason),
 }
@@ -153,6 +191,7 @@ impl Display for ChildState {
             Self::Open => write!(f, "Child is open"),
             Self::Destroying => write!(f, "Child is being destroyed"),
             Self::Closed => write!(f, "Closed"),
+            Self::Offline => write!(f, "Offline"),
         }
     }
 }
@@ -167,6 +206,9 @@ pub struct NexusChild<'c> {
     /// previous state of the child
     #[serde(skip_serializing)]
     prev_state: AtomicCell<ChildState>,
+    /// most recent state transitions, oldest first
+    #[serde(skip_serializing)]
+    state_history: Mutex<VecDeque<ChildStateTransition>>,
     /// TODO
     #[serde(skip_serializing)]
     remove_channel: (mpsc::Sender<()>, mpsc::Receiver<()>),
@@ -200,6 +242,16 @@ impl Display for NexusChild<'_> {
 
 impl<'c> NexusChild<'c> {
     pub(crate) fn set_state(&self, state: ChildState) {
+        self.set_state_cause(state, None);
+    }
+
+    /// Changes the state of the child, recording what triggered the change
+    /// in its state history.
+    pub(crate) fn set_state_cause(
+        &self,
+        state: ChildState,
+        cause: Option<&str>,
+    ) {
         let prev_state = self.state.swap(state);
         self.prev_state.store(prev_state);
         trace!(
@@ -209,6 +261,22 @@ impl<'c> NexusChild<'c> {
             prev_state.to_string(),
             state.to_string(),
         );
+
+        let mut history = self.state_history.lock();
+        if history.len() == STATE_HISTORY_LEN {
+            history.pop_front();
+        }
+        history.push_back(ChildStateTransition {
+            from: prev_state,
+            to: state,
+            cause: cause.map(String::from),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+
+    /// Returns the most recent state transitions of the child, oldest first.
+    pub fn state_history(&self) -> Vec<ChildStateTransition> {
+        self.state_history.lock().iter().cloned().collect()
     }
 
     /// Open the child in RW mode and claim the device to be ours. If the child
@@ -337,6 +405,85 @@ impl<'c> NexusChild<'c> {
         Ok(())
     }
 
+    /// Preempt the reservation held with `preempt_key` by another host,
+    /// acquiring a reservation of the given type with our registered `key`.
+    pub(crate) async fn resv_preempt(
+        &self,
+        key: u64,
+        preempt_key: u64,
+        resv_type: u8,
+    ) -> Result<(), ChildError> {
+        let hdl = self.get_io_handle().context(HandleOpen {})?;
+        self.resv_acquire(
+            &*hdl,
+            key,
+            preempt_key,
+            nvme_reservation_acquire_action::PREEMPT,
+            resv_type,
+        )
+        .await
+    }
+
+    /// Read the SMART / health information of the child's NVMe device.
+    pub(crate) async fn health(&self) -> Result<ChildHealth, ChildError> {
+        let hdl = self.get_io_handle().context(HandleOpen {})?;
+        let mut buffer = hdl
+            .dma_malloc(HEALTH_LOG_PAGE_SIZE)
+            .context(HandleDmaMalloc {})?;
+        hdl.nvme_get_log_page(HEALTH_LOG_PAGE, &mut buffer)
+            .await
+            .context(HealthLogPage {})?;
+        Ok(ChildHealth::from_log_page(buffer.as_slice()))
+    }
+
+    /// Check that the child is reachable and writable by reading the block
+    /// at the given offset in bytes, overwriting it with a different pattern
+    /// and reading that back. The original block is written back afterwards,
+    /// whether the check passed or not.
+    pub(crate) async fn probe(&self, offset: u64) -> Result<(), ChildError> {
+        let hdl = self.get_io_handle().context(HandleOpen {})?;
+        let block_len = hdl.get_device().block_len();
+        let mut original =
+            hdl.dma_malloc(block_len).context(HandleDmaMalloc {})?;
+        hdl.read_at(offset, &mut original)
+            .await
+            .context(ProbeRead {})?;
+
+        let mut buffer =
+            hdl.dma_malloc(block_len).context(HandleDmaMalloc {})?;
+        buffer.fill(!original.as_slice()[0]);
+        let result =
+            Self::probe_pattern(&*hdl, offset, block_len, &buffer).await;
+
+        let restored = hdl
+            .write_at(offset, &original)
+            .await
+            .context(ProbeWrite {})
+            .map(|_| ());
+        result.and(restored)
+    }
+
+    /// Writes the pattern to the block at the given offset and checks that
+    /// it reads back the same
+    async fn probe_pattern(
+        hdl: &dyn BlockDeviceHandle,
+        offset: u64,
+        block_len: u64,
+        pattern: &DmaBuf,
+    ) -> Result<(), ChildError> {
+        hdl.write_at(offset, pattern).await.context(ProbeWrite {})?;
+
+        let mut check =
+            hdl.dma_malloc(block_len).context(HandleDmaMalloc {})?;
+        hdl.read_at(offset, &mut check)
+            .await
+            .context(ProbeRead {})?;
+        if check.as_slice() != pattern.as_slice() {
+            return Err(ChildError::ProbeMismatch {});
+        }
+        Ok(())
+    }
+
     /// Get NVMe reservation report
     /// Returns: (key, host id) of write exclusive reservation holder
     async fn resv_report(
@@ -508,6 +655,7 @@ impl<'c> NexusChild<'c> {
                 e.verbose()
             );
         }
+        self.set_state(ChildState::Offline);
     }
 
     /// Get full name of this Nexus child.
@@ -527,10 +675,10 @@ impl<'c> NexusChild<'c> {
         &mut self,
         parent_size: u64,
     ) -> Result<String, ChildError> {
-        // Only online a child if it was previously set offline. Check for a
-        // "Closed" state as that is what offlining a child will set it to.
+        // Only online a child if it was previously set offline, or closed
+        // after its device went away.
         match self.state.load() {
-            ChildState::Closed => {
+            ChildState::Offline | ChildState::Closed => {
                 // Re-create the block device as it will have been previously
                 // destroyed.
                 let name = device_create(&self.name).await.context(
@@ -547,7 +695,7 @@ impl<'c> NexusChild<'c> {
                     );
                 }
             }
-            _ => return Err(ChildError::ChildNotClosed {}),
+            _ => return Err(ChildError::ChildNotOffline {}),
         }
 
         let result = self.open(parent_size);
@@ -704,6 +852,7 @@ impl<'c> NexusChild<'c> {
             device_descriptor: None,
             state: AtomicCell::new(ChildState::Init),
             prev_state: AtomicCell::new(ChildState::Init),
+            state_history: Mutex::new(VecDeque::new()),
             remove_channel: mpsc::channel(0),
             _c: Default::default(),
         }
@@ -817,3 +966,47 @@ pub fn lookup_nexus_child(bdev_name: &str) -> Option<&mut NexusChild> {
     }
     None
 }
+
+#[cfg(test)]
+mod test {
+    use super::{ChildState, NexusChild, Reason, STATE_HISTORY_LEN};
+
+    #[test]
+    fn child_state_history_wraps() {
+        let child =
+            NexusChild::new("malloc:///m0".into(), "nexus0".into(), None);
+        assert!(child.state_history().is_empty());
+
+        let states = [
+            ChildState::Open,
+            ChildState::Offline,
+            ChildState::Closed,
+            ChildState::Faulted(Reason::OutOfSync),
+        ];
+        let changes = STATE_HISTORY_LEN + 5;
+        for i in 0 .. changes {
+            let cause = format!("change {}", i);
+            child.set_state_cause(states[i % states.len()], Some(&cause));
+        }
+
+        // only the most recent transitions are kept, oldest first
+        let history = child.state_history();
+        assert_eq!(history.len(), STATE_HISTORY_LEN);
+        for (entry, i) in history.iter().zip(changes - STATE_HISTORY_LEN ..) {
+            assert_eq!(entry.cause, Some(format!("change {}", i)));
+            assert_eq!(entry.to, states[i % states.len()]);
+            assert_eq!(entry.from, states[(i - 1) % states.len()]);
+        }
+        assert_eq!(history.last().unwrap().to, child.state());
+
+        // a transition without a cause is recorded too
+        child.set_state(ChildState::Open);
+        let history = child.state_history();
+        assert_eq!(history.len(), STATE_HISTORY_LEN);
+        assert_eq!(history.last().unwrap().cause, None);
+        assert_eq!(
+            history[0].cause,
+            Some(format!("change {}", changes - STATE_HISTORY_LEN + 1))
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//!
+//! Self-healing of a nexus: a child which faulted for good is replaced by a
+//! new one, which is then rebuilt, without waiting for an operator. Where
+//! the replacement comes from is up to the control plane, which registers
+//! a child replacer to provide it.
+
+use std::time::{Duration, Instant};
+
+use futures::future::LocalBoxFuture;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+
+use super::{nexus_lookup_mut, Nexus};
+use crate::core::Reactors;
+
+/// Provides a replacement for a faulted child, given the names of the nexus
+/// and of the faulted child, returning the uri of the new child if there is
+/// one.
+pub type ChildReplacer = dyn Fn(String, String) -> LocalBoxFuture<'static, Option<String>>
+    + Send
+    + Sync;
+
+static CHILD_REPLACER: OnceCell<Box<ChildReplacer>> = OnceCell::new();
+
+/// Registers the child replacer used by all nexuses which heal themselves.
+/// Only the first registration takes effect.
+pub fn set_child_replacer<F>(replacer: F)
+where
+    F: Fn(String, String) -> LocalBoxFuture<'static, Option<String>>
+        + Send
+        + Sync
+        + 'static,
+{
+    if CHILD_REPLACER.set(Box::new(replacer)).is_err() {
+        warn!("child replacer already registered");
+    }
+}
+
+/// Policy describing whether a nexus replaces its faulted children on its
+/// own.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct NexusHealPolicy {
+    /// replace children which faulted because of IO errors
+    pub auto_replace: bool,
+    /// minimum time in seconds between two replacements, so that a flapping
+    /// child does not cause endless replacements
+    pub cooldown_secs: u64,
+}
+
+impl Default for NexusHealPolicy {
+    fn default() -> Self {
+        Self {
+            auto_replace: false,
+            cooldown_secs: 300,
+        }
+    }
+}
+
+impl<'n> Nexus<'n> {
+    /// Returns the heal policy of the nexus.
+    pub fn heal_policy(&self) -> NexusHealPolicy {
+        self.heal_policy.load()
+    }
+
+    /// Sets the heal policy of the nexus, which applies to children faulting
+    /// from then on.
+    pub fn set_heal_policy(&self, policy: NexusHealPolicy) {
+        info!("{}: setting heal policy {:?}", self.name, policy);
+        self.heal_policy.store(policy);
+    }
+
+    /// Starts replacing a child which faulted for good in the background, if
+    /// the heal policy allows it and no replacement happened too recently.
+    pub(crate) fn start_heal(&self, child: &str) {
+        let policy = self.heal_policy();
+        if !policy.auto_replace {
+            return;
+        }
+        if CHILD_REPLACER.get().is_none() {
+            warn!(
+                "{}: cannot replace child {}, no child replacer registered",
+                self.name, child
+            );
+            return;
+        }
+        {
+            let mut last = self.last_heal.lock();
+            let cooldown = Duration::from_secs(policy.cooldown_secs);
+            if matches!(*last, Some(at) if at.elapsed() < cooldown) {
+                warn!(
+                    "{}: not replacing child {}, last replacement was less than {:?} ago",
+                    self.name, child, cooldown
+                );
+                return;
+            }
+            *last = Some(Instant::now());
+        }
+
+        info!("{}: replacing faulted child {}", self.name, child);
+        Reactors::master()
+            .send_future(heal_child(self.name.clone(), child.to_string()));
+    }
+}
+
+/// Asks the child replacer for a replacement of the faulted child, adds it
+/// to the nexus, which rebuilds it, and removes the faulted child.
+async fn heal_child(nexus_name: String, child: String) {
+    let replacer = match CHILD_REPLACER.get() {
+        Some(replacer) => replacer,
+        None => return,
+    };
+    let uri = match replacer(nexus_name.clone(), child.clone()).await {
+        Some(uri) => uri,
+        None => {
+            warn!("{}: no replacement for child {}", nexus_name, child);
+            return;
+        }
+    };
+
+    let mut nexus = match nexus_lookup_mut(&nexus_name) {
+        Some(nexus) => nexus,
+        None => return,
+    };
+    if let Err(error) = nexus.as_mut().add_child(&uri, false).await {
+        error!(
+            "{}: failed to add replacement {} for child {}: {}",
+            nexus_name, uri, child, error
+        );
+        return;
+    }
+    info!("{}: replaced child {} with {}", nexus_name, child, uri);
+    if let Err(error) = nexus.remove_child(&child).await {
+        error!(
+            "{}: failed to remove replaced child {}: {}",
+            nexus_name, child, error
+        );
+    }
+}
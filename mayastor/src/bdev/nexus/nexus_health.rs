@@ -0,0 +1,233 @@
+//!
+//! SMART / health information of the NVMe devices backing the children of a
+//! nexus, so that failing disks can be spotted before they cause rebuilds.
+
+use serde::Serialize;
+
+use super::{ChildState, Error, Nexus};
+
+/// log page identifier of the SMART / health information log page
+pub(crate) const HEALTH_LOG_PAGE: u8 = 0x02;
+/// size in bytes of the SMART / health information log page
+pub(crate) const HEALTH_LOG_PAGE_SIZE: u64 = 512;
+
+/// Health attributes of a child's NVMe device, as reported by its SMART /
+/// health information log page.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct ChildHealth {
+    /// critical warning bits, any set bit needs attention
+    pub critical_warning: u8,
+    /// composite temperature in kelvin
+    pub temperature: u16,
+    /// remaining spare capacity in percent
+    pub available_spare: u8,
+    /// spare capacity in percent below which a warning is raised
+    pub available_spare_threshold: u8,
+    /// estimate of the device life used in percent, may exceed 100
+    pub percentage_used: u8,
+    /// data read in units of 512000 bytes
+    pub data_units_read: u64,
+    /// data written in units of 512000 bytes
+    pub data_units_written: u64,
+    /// hours the device has been powered on
+    pub power_on_hours: u64,
+    /// number of unsafe shutdowns
+    pub unsafe_shutdowns: u64,
+    /// number of unrecovered data integrity errors
+    pub media_errors: u64,
+    /// number of error information log entries
+    pub num_err_log_entries: u64,
+}
+
+impl ChildHealth {
+    /// Parse the SMART / health information log page. The 128 bit counters
+    /// are truncated to their lower 64 bits.
+    pub(crate) fn from_log_page(page: &[u8]) -> Self {
+        let u64_at = |offset: usize| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&page[offset .. offset + 8]);
+            u64::from_le_bytes(bytes)
+        };
+        Self {
+            critical_warning: page[0],
+            temperature: u16::from_le_bytes([page[1], page[2]]),
+            available_spare: page[3],
+            available_spare_threshold: page[4],
+            percentage_used: page[5],
+            data_units_read: u64_at(32),
+            data_units_written: u64_at(48),
+            power_on_hours: u64_at(128),
+            unsafe_shutdowns: u64_at(144),
+            media_errors: u64_at(160),
+            num_err_log_entries: u64_at(176),
+        }
+    }
+
+    /// Combine two health reports into their worst case.
+    fn worst(self, other: &Self) -> Self {
+        Self {
+            critical_warning: self.critical_warning | other.critical_warning,
+            temperature: self.temperature.max(other.temperature),
+            available_spare: self.available_spare.min(other.available_spare),
+            available_spare_threshold: self
+                .available_spare_threshold
+                .max(other.available_spare_threshold),
+            percentage_used: self.percentage_used.max(other.percentage_used),
+            data_units_read: self.data_units_read.max(other.data_units_read),
+            data_units_written: self
+                .data_units_written
+                .max(other.data_units_written),
+            power_on_hours: self.power_on_hours.max(other.power_on_hours),
+            unsafe_shutdowns: self.unsafe_shutdowns.max(other.unsafe_shutdowns),
+            media_errors: self.media_errors.max(other.media_errors),
+            num_err_log_entries: self
+                .num_err_log_entries
+                .max(other.num_err_log_entries),
+        }
+    }
+}
+
+/// Health of a single child of a nexus
+#[derive(Debug, Serialize)]
+pub struct ChildHealthEntry {
+    /// name of the child
+    pub child: String,
+    /// health attributes, if they could be read
+    pub health: Option<ChildHealth>,
+    /// why the health attributes could not be read
+    pub error: Option<String>,
+}
+
+/// Health of all children of a nexus
+#[derive(Debug, Serialize)]
+pub struct NexusHealth {
+    /// worst case of the health attributes of all children
+    pub worst: Option<ChildHealth>,
+    /// health of the individual children
+    pub children: Vec<ChildHealthEntry>,
+}
+
+impl<'n> Nexus<'n> {
+    /// Read the SMART / health information of the given child.
+    pub async fn child_health(&self, name: &str) -> Result<ChildHealth, Error> {
+        let child = self
+            .children
+            .iter()
+            .find(|c| c.get_name() == name)
+            .ok_or_else(|| Error::ChildNotFound {
+                child: name.to_owned(),
+                name: self.name.clone(),
+            })?;
+        child.health().await.map_err(|source| Error::ChildHealth {
+            source,
+            child: name.to_owned(),
+            name: self.name.clone(),
+        })
+    }
+
+    /// Read the SMART / health information of all open children and
+    /// aggregate them into a worst case view. Children which do not support
+    /// it, e.g. non NVMe devices, are reported with an error.
+    pub async fn health(&self) -> NexusHealth {
+        let mut children = Vec::new();
+        for child in self
+            .children
+            .iter()
+            .filter(|c| c.state() == ChildState::Open)
+        {
+            let (health, error) = match child.health().await {
+                Ok(health) => (Some(health), None),
+                Err(error) => (None, Some(error.to_string())),
+            };
+            children.push(ChildHealthEntry {
+                child: child.get_name().to_string(),
+                health,
+                error,
+            });
+        }
+
+        NexusHealth {
+            worst: children
+                .iter()
+                .filter_map(|c| c.health.clone())
+                .reduce(|worst, h| worst.worst(&h)),
+            children,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ChildHealth, HEALTH_LOG_PAGE_SIZE};
+
+    /// A log page as a device would return it, with every field set to a
+    /// distinct value and the upper halves of the 128 bit counters set.
+    fn log_page() -> Vec<u8> {
+        let mut page = vec![0u8; HEALTH_LOG_PAGE_SIZE as usize];
+        page[0] = 0x05;
+        page[1 .. 3].copy_from_slice(&310u16.to_le_bytes());
+        page[3] = 90;
+        page[4] = 10;
+        page[5] = 104;
+        let counters = [
+            (32, 0x1122_3344_5566_7788u64),
+            (48, 0x0102_0304_0506_0708),
+            (128, 26_280),
+            (144, 7),
+            (160, 3),
+            (176, 12),
+        ];
+        for (offset, value) in counters.iter() {
+            page[*offset .. offset + 8].copy_from_slice(&value.to_le_bytes());
+            page[offset + 8 .. offset + 16].copy_from_slice(&[0xff; 8]);
+        }
+        page
+    }
+
+    #[test]
+    fn health_log_page_parse() {
+        let health = ChildHealth::from_log_page(&log_page());
+        assert_eq!(
+            health,
+            ChildHealth {
+                critical_warning: 0x05,
+                temperature: 310,
+                available_spare: 90,
+                available_spare_threshold: 10,
+                percentage_used: 104,
+                data_units_read: 0x1122_3344_5566_7788,
+                data_units_written: 0x0102_0304_0506_0708,
+                power_on_hours: 26_280,
+                unsafe_shutdowns: 7,
+                media_errors: 3,
+                num_err_log_entries: 12,
+            }
+        );
+
+        // an all zero page, e.g. a brand new device, parses to defaults
+        let empty = vec![0u8; HEALTH_LOG_PAGE_SIZE as usize];
+        assert_eq!(ChildHealth::from_log_page(&empty), ChildHealth::default());
+    }
+
+    #[test]
+    fn health_worst_case() {
+        let failing = ChildHealth::from_log_page(&log_page());
+        let healthy = ChildHealth {
+            critical_warning: 0x08,
+            temperature: 300,
+            available_spare: 100,
+            available_spare_threshold: 5,
+            media_errors: 4,
+            ..Default::default()
+        };
+
+        let worst = healthy.worst(&failing);
+        assert_eq!(worst.critical_warning, 0x0d);
+        assert_eq!(worst.temperature, 310);
+        assert_eq!(worst.available_spare, 90);
+        assert_eq!(worst.available_spare_threshold, 10);
+        assert_eq!(worst.percentage_used, 104);
+        assert_eq!(worst.media_errors, 4);
+        assert_eq!(worst.power_on_hours, 26_280);
+    }
+}
@@ -18,6 +18,9 @@ use super::{
     NexusChannel,
     NexusChannelInner,
     NexusStatus,
+    NexusTrimPolicy,
+    OnInsufficientReplicas,
+    WriteAckPolicy,
     NEXUS_PRODUCT_ID,
 };
 
@@ -35,6 +38,9 @@ use crate::core::{
     Reactors,
 };
 
+/// Force Unit Access bit in dword 12 of NVMe read and write commands
+const NVME_CDW12_FUA: u32 = 1 << 30;
+
 /// TODO
 #[repr(C)]
 #[derive(Debug)]
@@ -48,6 +54,10 @@ pub(crate) struct NioCtx {
     channel: spdk_rs::IoChannel<NexusChannel>,
     /// the IO must fail regardless of when it completes
     must_fail: bool,
+    /// number of children the IO was meant to be submitted to
+    submitted: u8,
+    /// number of child IO's which completed successfully
+    succeeded: u8,
 }
 
 /// TODO
@@ -97,22 +107,29 @@ impl<'n> NexusBio<'n> {
         ctx.status = IoStatus::Pending;
         ctx.in_flight = 0;
         ctx.must_fail = false;
+        ctx.submitted = 0;
+        ctx.succeeded = 0;
         bio
     }
 
     /// TODO
     fn submit_request(mut self) {
+        if self.rejected_by_replica_policy() {
+            trace!(?self, "rejected by replica policy");
+            self.fail();
+            return;
+        }
+
         if let Err(_e) = match self.io_type() {
             IoType::Read => self.readv(),
             // these IOs are submitted to all the underlying children
-            IoType::Write
-            | IoType::WriteZeros
-            | IoType::Reset
-            | IoType::Unmap => self.submit_all(),
-            IoType::Flush => {
-                self.ok();
-                Ok(())
+            IoType::Write | IoType::WriteZeros | IoType::Reset => {
+                self.submit_all()
             }
+            IoType::Unmap => self.submit_trim(),
+            // flushes are only acknowledged once every child has its data
+            // on stable storage
+            IoType::Flush => self.submit_all(),
             IoType::NvmeAdmin => {
                 self.fail();
                 Err(CoreError::NotSupported {
@@ -131,6 +148,64 @@ impl<'n> NexusBio<'n> {
         }
     }
 
+    /// Submit an unmap to the children as the trim policy of the nexus
+    /// dictates.
+    fn submit_trim(&mut self) -> Result<(), CoreError> {
+        match self.nexus_as_ref().trim_policy() {
+            NexusTrimPolicy::PropagateAll => self.submit_all(),
+            NexusTrimPolicy::BestEffort
+                if self
+                    .inner_channel()
+                    .writers
+                    .iter()
+                    .any(|h| Self::supports_unmap(h.as_ref())) =>
+            {
+                self.submit_all()
+            }
+            NexusTrimPolicy::BestEffort | NexusTrimPolicy::Drop => {
+                self.ok();
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the block range modified by this IO, if it modifies any.
+    #[inline]
+    fn written_blocks(&self) -> Option<(u64, u64)> {
+        match self.io_type() {
+            IoType::Write | IoType::WriteZeros | IoType::Unmap => {
+                Some((self.offset(), self.num_blocks()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Determine if the child behind the handle supports unmap.
+    #[inline]
+    fn supports_unmap(hdl: &dyn BlockDeviceHandle) -> bool {
+        hdl.get_device().io_type_supported(IoType::Unmap)
+    }
+
+    /// Determine if the replica policy of the nexus forbids this IO because
+    /// fewer children are healthy than the policy requires. Only open
+    /// children are readers, so the number of readers of our channel is the
+    /// number of healthy children as seen from this core.
+    #[inline]
+    fn rejected_by_replica_policy(&self) -> bool {
+        let policy = self.nexus_as_ref().replica_policy();
+        if self.inner_channel().readers.len() as u32 >= policy.min_healthy {
+            return false;
+        }
+
+        match policy.on_insufficient {
+            OnInsufficientReplicas::Fault => true,
+            OnInsufficientReplicas::ReadOnly => matches!(
+                self.io_type(),
+                IoType::Write | IoType::WriteZeros | IoType::Unmap
+            ),
+        }
+    }
+
     /// assess the IO if we need to mark it failed or ok.
     /// obtain the Nexus struct embedded within the bdev
     pub(crate) fn nexus_as_ref(&self) -> Pin<&Nexus> {
@@ -170,6 +245,7 @@ impl<'n> NexusBio<'n> {
         self.ctx_mut().in_flight -= 1;
 
         if success {
+            self.ctx_mut().succeeded += 1;
             self.ok_checked();
         } else {
             // IO failure, mark the IO failed and take the child out
@@ -191,7 +267,7 @@ impl<'n> NexusBio<'n> {
     #[inline]
     fn ok_checked(&mut self) {
         if self.ctx().in_flight == 0 {
-            if self.ctx().must_fail {
+            if self.ctx().must_fail && !self.acked_on_quorum() {
                 //warn!(?self, "resubmitted due to must_fail");
                 self.retry_checked();
                 //self.fail();
@@ -205,10 +281,37 @@ impl<'n> NexusBio<'n> {
     #[inline]
     fn fail_checked(&mut self) {
         if self.ctx().in_flight == 0 {
-            self.fail();
+            if self.acked_on_quorum() {
+                self.ok();
+            } else {
+                self.fail();
+            }
         }
     }
 
+    /// Determine if a write which failed on some children is acknowledged
+    /// anyway, because the write ack policy of the nexus only requires it to
+    /// succeed on a majority of the children.
+    fn acked_on_quorum(&self) -> bool {
+        if self.written_blocks().is_none()
+            || self.nexus_as_ref().write_ack_policy()
+                != WriteAckPolicy::AckOnQuorum
+        {
+            return false;
+        }
+        let ctx = self.ctx();
+        if u32::from(ctx.succeeded) * 2 <= u32::from(ctx.submitted) {
+            return false;
+        }
+        warn!(
+            ?self,
+            "acknowledging write which succeeded on {} of {} children",
+            ctx.succeeded,
+            ctx.submitted
+        );
+        true
+    }
+
     /// retry this IO when all other IOs have completed
     #[inline]
     fn retry_checked(&mut self) {
@@ -280,6 +383,7 @@ impl<'n> NexusBio<'n> {
                     "(core: {} thread: {}): read IO to {} submission failed with error {:?}",
                     Cores::current(), Mthread::current().unwrap().name(), device, r);
 
+                self.nexus_as_ref().track_for_reconnect(&device, None);
                 let inner = self.inner_channel_mut();
                 let must_retire = inner.fault_child(&device);
                 if must_retire {
@@ -331,14 +435,51 @@ impl<'n> NexusBio<'n> {
         }
     }
 
+    /// Returns true if the IO is a write which must be on stable storage
+    /// before it completes, as the FUA bit of the NVMe command which it
+    /// came from asks.
+    #[inline]
+    fn is_fua(&self) -> bool {
+        matches!(self.io_type(), IoType::Write)
+            && unsafe { (*self.as_ptr()).u.bdev.nvme_cdw12.raw }
+                & NVME_CDW12_FUA
+                != 0
+    }
+
+    /// submit a write to one of the children, passing on the FUA bit so
+    /// that each child has the data on stable storage before it completes
     #[inline]
     fn submit_write(
         &self,
         hdl: &dyn BlockDeviceHandle,
     ) -> Result<(), CoreError> {
-        hdl.writev_blocks(
-            self.iovs(),
-            self.iov_count(),
+        if self.is_fua() {
+            hdl.writev_blocks_fua(
+                self.iovs(),
+                self.iov_count(),
+                self.offset() + self.data_ent_offset(),
+                self.num_blocks(),
+                Self::child_completion,
+                self.as_ptr().cast(),
+            )
+        } else {
+            hdl.writev_blocks(
+                self.iovs(),
+                self.iov_count(),
+                self.offset() + self.data_ent_offset(),
+                self.num_blocks(),
+                Self::child_completion,
+                self.as_ptr().cast(),
+            )
+        }
+    }
+
+    #[inline]
+    fn submit_unmap(
+        &self,
+        hdl: &dyn BlockDeviceHandle,
+    ) -> Result<(), CoreError> {
+        hdl.unmap_blocks(
             self.offset() + self.data_ent_offset(),
             self.num_blocks(),
             Self::child_completion,
@@ -347,11 +488,11 @@ impl<'n> NexusBio<'n> {
     }
 
     #[inline]
-    fn submit_unmap(
+    fn submit_write_zeroes(
         &self,
         hdl: &dyn BlockDeviceHandle,
     ) -> Result<(), CoreError> {
-        hdl.unmap_blocks(
+        hdl.write_zeroes(
             self.offset() + self.data_ent_offset(),
             self.num_blocks(),
             Self::child_completion,
@@ -360,11 +501,11 @@ impl<'n> NexusBio<'n> {
     }
 
     #[inline]
-    fn submit_write_zeroes(
+    fn submit_flush(
         &self,
         hdl: &dyn BlockDeviceHandle,
     ) -> Result<(), CoreError> {
-        hdl.write_zeroes(
+        hdl.flush_io(
             self.offset() + self.data_ent_offset(),
             self.num_blocks(),
             Self::child_completion,
@@ -386,16 +527,37 @@ impl<'n> NexusBio<'n> {
     /// avoid double frees. This function handles IO for a subset that must
     /// be submitted to all the underlying children.
     fn submit_all(&mut self) -> Result<(), CoreError> {
+        if !matches!(self.io_type(), IoType::Reset | IoType::Flush) {
+            // offline children miss this write and need it rebuilt later
+            self.nexus_as_ref()
+                .mark_dirty(self.offset(), self.num_blocks());
+        }
+
         let mut inflight = 0;
+        // a retried IO starts counting afresh
+        self.ctx_mut().must_fail = false;
+        self.ctx_mut().submitted = self.inner_channel().writers.len() as u8;
+        self.ctx_mut().succeeded = 0;
         // Name of the device which experiences I/O submission failures.
         let mut failed_device = None;
 
+        let best_effort = matches!(
+            self.nexus_as_ref().trim_policy(),
+            NexusTrimPolicy::BestEffort
+        );
+
         let result = self.inner_channel().writers.iter().try_for_each(|h| {
             match self.io_type() {
                 IoType::Write => self.submit_write(h.as_ref()),
+                IoType::Unmap
+                    if best_effort && !Self::supports_unmap(h.as_ref()) =>
+                {
+                    return Ok(());
+                }
                 IoType::Unmap => self.submit_unmap(h.as_ref()),
                 IoType::WriteZeros => self.submit_write_zeroes(h.as_ref()),
                 IoType::Reset => self.submit_reset(h.as_ref()),
+                IoType::Flush => self.submit_flush(h.as_ref()),
                 // we should never reach here, if we do it is a bug.
                 _ => unreachable!(),
             }
@@ -425,6 +587,8 @@ impl<'n> NexusBio<'n> {
         // device should not be retired in case of ENOMEM.
         if result.is_err() {
             let device = failed_device.unwrap();
+            self.nexus_as_ref()
+                .track_for_reconnect(&device, self.written_blocks());
             // set the IO as failed in the submission stage.
             self.ctx_mut().must_fail = true;
             if self.inner_channel_mut().remove_child(&device) {
@@ -496,6 +660,10 @@ impl<'n> NexusBio<'n> {
         );
 
         let child = child.device_name();
+        warn!(?self, ?status, "IO failed on child {}", child);
+        // writes the child missed are rebuilt if it is reconnected
+        self.nexus_as_ref()
+            .track_for_reconnect(&child, self.written_blocks());
         // check if this child needs to be retired
         let needs_retire = self.inner_channel_mut().fault_child(&child);
         // The child state was not faulted yet, so this is the first IO
@@ -526,6 +694,7 @@ pub(crate) fn nexus_submit_request(
 async fn nexus_child_retire(nexus_name: String, device: String) {
     if let Some(mut nexus) = nexus_lookup_mut(&nexus_name) {
         warn!(?nexus, ?device, "retiring child");
+        let child = nexus.lookup_child(&device).map(|c| c.name.clone());
 
         if let Err(e) = nexus.as_mut().child_retire(device.clone()).await {
             error!(?e, "double pause which we cant sneak in...");
@@ -534,6 +703,12 @@ async fn nexus_child_retire(nexus_name: String, device: String) {
             return;
         }
 
+        if let Some(child) = child {
+            if !nexus.start_reconnect(&child) {
+                nexus.start_heal(&child);
+            }
+        }
+
         if matches!(nexus.status(), NexusStatus::Faulted) {
             warn!(?nexus, "no children left");
         }
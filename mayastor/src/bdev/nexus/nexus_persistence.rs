@@ -1,5 +1,9 @@
 use super::{ChildState, Nexus, NexusChild};
-use crate::{persistent_store::PersistentStore, sleep::mayastor_sleep};
+use crate::{
+    persistent_store::PersistentStore,
+    rebuild::RebuildMap,
+    sleep::mayastor_sleep,
+};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
@@ -13,6 +17,10 @@ pub struct NexusInfo {
     pub clean_shutdown: bool,
     /// Information about children.
     pub children: Vec<ChildInfo>,
+    /// Size of the nexus in bytes, once it has been resized. A nexus created
+    /// again with a larger size keeps this one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
 }
 
 /// Definition of the child information that gets saved in the persistent
@@ -23,6 +31,10 @@ pub struct ChildInfo {
     pub uuid: String,
     /// Child's state of health.
     pub healthy: bool,
+    /// Regions written while the child was not receiving writes, saved
+    /// when the nexus is destroyed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dirty_regions: Option<RebuildMap>,
 }
 
 /// Defines the type of persist operations.
@@ -35,6 +47,8 @@ pub(crate) enum PersistOp {
     Update((ChildUri, ChildState)),
     /// Save the clean shutdown variable.
     Shutdown,
+    /// Save the new size of the nexus.
+    Resize(u64),
 }
 
 impl<'n> Nexus<'n> {
@@ -58,6 +72,7 @@ impl<'n> Nexus<'n> {
                         uuid: NexusChild::uuid(&c.name)
                             .expect("Failed to get child UUID."),
                         healthy: Self::child_healthy(&c.state()),
+                        dirty_regions: None,
                     };
                     nexus_info.children.push(child_info);
                 });
@@ -69,6 +84,7 @@ impl<'n> Nexus<'n> {
                     uuid: NexusChild::uuid(&uri)
                         .expect("Failed to get child UUID."),
                     healthy: Self::child_healthy(&state),
+                    dirty_regions: None,
                 };
                 nexus_info.children.push(child_info);
             }
@@ -85,15 +101,65 @@ impl<'n> Nexus<'n> {
                 });
             }
             PersistOp::Shutdown => {
-                // Only update the clean shutdown variable. Do not update the
-                // child state information.
+                // Only update the clean shutdown variable and the regions
+                // the children missed. Do not update the child state
+                // information.
                 // This should only be called when destroying a nexus.
+                for (uuid, map) in self.dirty_regions_snapshot() {
+                    match nexus_info
+                        .children
+                        .iter_mut()
+                        .find(|c| c.uuid == uuid)
+                    {
+                        Some(child_info) => {
+                            child_info.dirty_regions = Some(map);
+                        }
+                        None => nexus_info.children.push(ChildInfo {
+                            uuid,
+                            healthy: false,
+                            dirty_regions: Some(map),
+                        }),
+                    }
+                }
                 nexus_info.clean_shutdown = true;
             }
+            PersistOp::Resize(size) => {
+                // Only update the size of the nexus.
+                nexus_info.size = Some(size);
+            }
         }
         self.save(&nexus_info).await;
     }
 
+    /// Load the information saved by a previous instance of the nexus, if
+    /// there is any.
+    pub(crate) async fn load_persisted(&self) -> Option<NexusInfo> {
+        if !PersistentStore::enabled() {
+            return None;
+        }
+
+        let nexus_uuid = self.uuid().to_string();
+        match PersistentStore::get(&nexus_uuid).await {
+            Ok(value) => match serde_json::from_value(value) {
+                Ok(info) => Some(info),
+                Err(e) => {
+                    warn!(
+                        "Ignoring persisted information of nexus {} with error {}",
+                        self.name, e
+                    );
+                    None
+                }
+            },
+            Err(e) => {
+                debug!(
+                    "No persisted information for nexus {}: {}",
+                    self.name, e
+                );
+                None
+            }
+        }
+    }
+
     /// Determine child health.
     fn child_healthy(state: &ChildState) -> bool {
         state == &ChildState::Open
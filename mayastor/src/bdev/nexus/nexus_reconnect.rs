@@ -0,0 +1,199 @@
+//!
+//! Reconnect remote nvmf children which faulted because of an IO error, for
+//! example during a brief network interruption. While a child is away the
+//! nexus records the regions written to, so that only those are rebuilt
+//! once the child is back instead of the whole child.
+
+use std::{cmp::min, pin::Pin, time::Duration};
+
+use serde::Serialize;
+use url::Url;
+
+use super::{nexus_lookup_mut, ChildState, Error, Nexus, NexusStatus, Reason};
+use crate::{core::Reactors, sleep::mayastor_sleep, subsys::Config};
+
+/// longest delay between two reconnect attempts
+const CHILD_RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Reconnect attempts made for a child of a nexus
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ChildReconnect {
+    /// name of the child
+    pub child: String,
+    /// reconnect attempts made so far
+    pub attempts: u32,
+    /// true while the child is being reconnected
+    pub reconnecting: bool,
+}
+
+impl<'n> Nexus<'n> {
+    /// Returns true if the child is reconnected when it faults because of an
+    /// IO error: only remote nvmf children are, if reconnects are enabled.
+    fn reconnect_enabled(&self, child: &str) -> bool {
+        Config::get().nexus_opts.child_reconnect_attempts > 0
+            && Url::parse(child).map_or(false, |url| url.scheme() == "nvmf")
+    }
+
+    /// Called when IO to the device of a child failed. If the child is going
+    /// to be reconnected, start recording the regions written to the nexus,
+    /// including the given write, which the child may have missed.
+    pub(crate) fn track_for_reconnect(
+        &self,
+        device: &str,
+        write: Option<(u64, u64)>,
+    ) {
+        match self.lookup_child(device) {
+            Some(child) if self.reconnect_enabled(&child.name) => {
+                self.start_dirty_tracking(&child.name);
+            }
+            _ => return,
+        }
+        if let Some((offset, num_blocks)) = write {
+            self.mark_dirty(offset, num_blocks);
+        }
+    }
+
+    /// Returns the reconnect attempts made for the children of the nexus.
+    pub fn child_reconnects(&self) -> Vec<ChildReconnect> {
+        self.reconnects.lock().values().cloned().collect()
+    }
+
+    /// Starts reconnecting a child in the background after it has been
+    /// retired because of an IO error, if it is to be reconnected. Returns
+    /// false if it is not.
+    pub(crate) fn start_reconnect(&self, child: &str) -> bool {
+        if !self.reconnect_enabled(child) {
+            return false;
+        }
+        {
+            let mut reconnects = self.reconnects.lock();
+            let entry =
+                reconnects.entry(child.to_string()).or_insert_with(|| {
+                    ChildReconnect {
+                        child: child.to_string(),
+                        ..Default::default()
+                    }
+                });
+            if entry.reconnecting {
+                return true;
+            }
+            entry.reconnecting = true;
+        }
+
+        info!("{}: reconnecting child {}", self.name, child);
+        Reactors::master()
+            .send_future(reconnect_child(self.name.clone(), child.to_string()));
+        true
+    }
+
+    /// Marks whether the child is being reconnected, counting an attempt
+    /// when it is about to be made.
+    fn update_reconnect(&self, child: &str, reconnecting: bool, attempt: bool) {
+        if let Some(entry) = self.reconnects.lock().get_mut(child) {
+            entry.reconnecting = reconnecting;
+            if attempt {
+                entry.attempts += 1;
+            }
+        }
+    }
+
+    /// Brings back a child which faulted because of an IO error, rebuilding
+    /// the regions written while it was away.
+    async fn reconnect(
+        mut self: Pin<&mut Self>,
+        name: &str,
+    ) -> Result<NexusStatus, Error> {
+        self.as_mut()
+            .get_child_by_name(name)?
+            .set_state_cause(ChildState::Offline, Some("reconnect"));
+        self.update_reconnect(name, false, true);
+
+        let result = self.as_mut().online_child(name).await;
+        if result.is_err() {
+            self.update_reconnect(name, true, false);
+            if let Ok(child) = self.as_mut().get_child_by_name(name) {
+                if child.state() == ChildState::Offline {
+                    child.set_state_cause(
+                        ChildState::Faulted(Reason::IoError),
+                        Some("reconnect failed"),
+                    );
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Returns the delays to wait before each of the given number of reconnect
+/// attempts: the first delay, doubled after every attempt up to a limit.
+pub fn reconnect_delays(attempts: u32, first: Duration) -> Vec<Duration> {
+    let mut delay = first;
+    (0 .. attempts)
+        .map(|_| {
+            let current = delay;
+            delay = min(delay * 2, CHILD_RECONNECT_MAX_DELAY);
+            current
+        })
+        .collect()
+}
+
+/// Returns true if a child in the given state, if it is still there, is to
+/// be reconnected. It is not once it left the faulted state some other
+/// way, e.g. it is being removed.
+pub fn reconnect_wanted(state: Option<ChildState>) -> bool {
+    state == Some(ChildState::Faulted(Reason::IoError))
+}
+
+/// Tries to reconnect a child with an exponential backoff, until it is back
+/// or the configured number of attempts is used up. Gives up early if the
+/// child is no longer to be reconnected.
+async fn reconnect_child(nexus_name: String, child: String) {
+    let opts = &Config::get().nexus_opts;
+    let delays = reconnect_delays(
+        opts.child_reconnect_attempts,
+        Duration::from_millis(opts.child_reconnect_delay_ms),
+    );
+
+    for (attempt, delay) in (1 ..).zip(delays) {
+        if mayastor_sleep(delay).await.is_err() {
+            error!("Failed to wait for Mayastor sleep");
+        }
+
+        let nexus = match nexus_lookup_mut(&nexus_name) {
+            Some(nexus) => nexus,
+            None => return,
+        };
+        let state = nexus
+            .children
+            .iter()
+            .find(|c| c.name == child)
+            .map(|c| c.state());
+        if !reconnect_wanted(state) {
+            break;
+        }
+
+        match nexus.reconnect(&child).await {
+            Ok(_) => {
+                info!(
+                    "{}: reconnected child {} after {} attempts",
+                    nexus_name, child, attempt
+                );
+                return;
+            }
+            Err(error) => warn!(
+                "{}: attempt {} to reconnect child {} failed: {}",
+                nexus_name, attempt, child, error
+            ),
+        }
+    }
+
+    if let Some(nexus) = nexus_lookup_mut(&nexus_name) {
+        warn!("{}: stopped reconnecting child {}", nexus_name, child);
+        nexus.update_reconnect(&child, false, false);
+        if nexus.children.iter().any(|c| {
+            c.name == child && c.state() == ChildState::Faulted(Reason::IoError)
+        }) {
+            nexus.start_heal(&child);
+        }
+    }
+}
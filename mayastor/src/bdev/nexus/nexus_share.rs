@@ -165,6 +165,31 @@ impl<'n> Nexus<'n> {
         }
     }
 
+    /// Share the nexus with the given protocol, replacing the current target
+    /// if the nexus is already shared with a different protocol. Only the
+    /// target is torn down and recreated, the nexus and its children are
+    /// left untouched so no rebuild is needed.
+    pub async fn reshare(
+        mut self: Pin<&mut Self>,
+        protocol: ShareProtocolNexus,
+        key: Option<String>,
+    ) -> Result<String, Error> {
+        if let Some(target) = &self.nexus_target {
+            let current = ShareProtocolNexus::from(target);
+            if current == protocol {
+                return Ok(self.get_share_uri().unwrap());
+            }
+
+            info!(
+                "{}: resharing from {:?} to {:?}",
+                self.name, current, protocol
+            );
+            self.as_mut().unshare_nexus().await?;
+        }
+
+        self.share(protocol, key).await
+    }
+
     pub async fn unshare_nexus(mut self: Pin<&mut Self>) -> Result<(), Error> {
         unsafe {
             match self.as_mut().get_unchecked_mut().nexus_target.take() {
@@ -0,0 +1,190 @@
+//!
+//! Reconcile the sizes of the children of a nexus. A resize which failed
+//! on some of the replicas leaves children which are smaller than the
+//! others; those which are local lvols are grown to match the largest
+//! child. When the nexus is shrunk, the children which stay larger are
+//! reported.
+
+use std::convert::TryFrom;
+
+use serde::Serialize;
+
+use super::Nexus;
+use crate::{
+    core::{partition, Bdev},
+    lvs::Lvol,
+};
+
+/// A child whose size could not be reconciled with the nexus
+#[derive(Debug, Clone, Serialize)]
+pub struct UnreconciledChild {
+    /// name of the child
+    pub child: String,
+    /// size of the child in blocks, if it is known
+    pub num_blocks: Option<u64>,
+    /// why the child could not be reconciled
+    pub reason: String,
+}
+
+/// Outcome of reconciling the sizes of the children of a nexus
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ChildSizeReconcile {
+    /// children which were smaller than the largest child
+    pub lagging: Vec<String>,
+    /// children which were resized
+    pub resized: Vec<String>,
+    /// children which are still smaller than the largest child, or whose
+    /// size is not known
+    pub unreconciled: Vec<UnreconciledChild>,
+}
+
+impl<'n> Nexus<'n> {
+    /// Returns the size in blocks of the device of the given child, if the
+    /// child has one.
+    fn child_num_blocks(&self, name: &str) -> Option<u64> {
+        self.children
+            .iter()
+            .find(|c| c.name == name)
+            .and_then(|c| c.get_device().ok())
+            .map(|dev| dev.num_blocks())
+    }
+
+    /// Returns true if a child of the given size holds the data partition
+    /// of the nexus.
+    fn fits_child(&self, num_blocks: u64) -> bool {
+        let block_len = u64::from(self.bdev().block_len());
+        match partition::calc_data_partition(
+            self.req_size,
+            num_blocks,
+            block_len,
+        ) {
+            Some((start, end)) => {
+                start == self.data_ent_offset
+                    && end - start >= self.bdev().num_blocks()
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the children which keep a larger size than a nexus of
+    /// `size` bytes needs. They cannot be shrunk in place, as SPDK refuses
+    /// to shrink a device which is open and the nexus keeps its children
+    /// open.
+    pub(crate) fn oversized_children(
+        &self,
+        size: u64,
+    ) -> Vec<UnreconciledChild> {
+        let block_len = self.block_len();
+        let gpt_blocks = partition::bytes_to_alinged_blocks(
+            partition::GPT_TABLE_SIZE,
+            block_len,
+        );
+        let needed = self.data_ent_offset + size / block_len + gpt_blocks + 1;
+
+        self.children
+            .iter()
+            .filter_map(|child| {
+                let num_blocks = self.child_num_blocks(&child.name)?;
+                if num_blocks <= needed {
+                    return None;
+                }
+                Some(UnreconciledChild {
+                    child: child.name.clone(),
+                    num_blocks: Some(num_blocks),
+                    reason: format!(
+                        "the child is open and cannot be shrunk in place, \
+                         {} blocks are needed",
+                        needed
+                    ),
+                })
+            })
+            .collect()
+    }
+
+    /// Compares the size of every child with the largest child and grows
+    /// the children which lag behind, as well as those which are too small
+    /// for the nexus, to the size of the largest child. Only children which
+    /// are local lvols can be grown, the others are reported as
+    /// unreconciled.
+    pub async fn reconcile_child_sizes(&self) -> ChildSizeReconcile {
+        let mut report = ChildSizeReconcile::default();
+
+        let mut sizes = Vec::new();
+        for child in self.children.iter() {
+            match child.get_device() {
+                Ok(dev) => sizes.push((
+                    child.name.clone(),
+                    dev.device_name(),
+                    dev.size_in_bytes(),
+                    dev.num_blocks(),
+                )),
+                Err(error) => report.unreconciled.push(UnreconciledChild {
+                    child: child.name.clone(),
+                    num_blocks: None,
+                    reason: error.to_string(),
+                }),
+            }
+        }
+        let largest = sizes
+            .iter()
+            .map(|(_, _, size, _)| *size)
+            .max()
+            .unwrap_or_default();
+
+        for (child, device, size, num_blocks) in sizes {
+            if size >= largest && self.fits_child(num_blocks) {
+                continue;
+            }
+            warn!(
+                "{}: child {} of {} bytes lags behind the largest child, resizing it to {} bytes",
+                self.name, child, size, largest
+            );
+            report.lagging.push(child.clone());
+
+            let result = match Bdev::lookup_by_name(&device).map(Lvol::try_from)
+            {
+                Some(Ok(lvol)) => lvol
+                    .resize(largest)
+                    .await
+                    .map_err(|error| error.to_string()),
+                Some(Err(error)) => Err(error.to_string()),
+                None => Err(format!("device {} not found", device)),
+            };
+
+            let num_blocks = self.child_num_blocks(&child);
+            let caught_up = self
+                .children
+                .iter()
+                .find(|c| c.name == child)
+                .and_then(|c| c.get_device().ok())
+                .map_or(false, |dev| {
+                    dev.size_in_bytes() >= largest
+                        && self.fits_child(dev.num_blocks())
+                });
+            match result {
+                Ok(()) if caught_up => {
+                    info!("{}: resized child {}", self.name, child);
+                    report.resized.push(child);
+                }
+                Ok(()) => report.unreconciled.push(UnreconciledChild {
+                    child,
+                    num_blocks,
+                    reason: "child is still too small after resizing".into(),
+                }),
+                Err(reason) => {
+                    error!(
+                        "{}: failed to resize child {}: {}",
+                        self.name, child, reason
+                    );
+                    report.unreconciled.push(UnreconciledChild {
+                        child,
+                        num_blocks,
+                        reason,
+                    });
+                }
+            }
+        }
+
+        report
+    }
+}
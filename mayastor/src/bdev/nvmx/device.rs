@@ -170,6 +170,13 @@ impl BlockDevice for NvmeBlockDevice {
         self.ns.alignment()
     }
 
+    fn max_io_size(&self) -> Option<u64> {
+        match self.ns.max_io_xfer_size() {
+            0 => None,
+            size => Some(size),
+        }
+    }
+
     fn io_type_supported(&self, io_type: IoType) -> bool {
         // bdev_nvme_io_type_supported
         match io_type {
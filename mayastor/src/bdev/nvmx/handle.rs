@@ -17,11 +17,13 @@ use spdk_rs::{
         spdk_nvme_ctrlr_cmd_io_raw,
         spdk_nvme_dsm_range,
         spdk_nvme_ns_cmd_dataset_management,
+        spdk_nvme_ns_cmd_flush,
         spdk_nvme_ns_cmd_read,
         spdk_nvme_ns_cmd_readv,
         spdk_nvme_ns_cmd_write,
         spdk_nvme_ns_cmd_write_zeroes,
         spdk_nvme_ns_cmd_writev,
+        SPDK_NVME_IO_FLAGS_FORCE_UNIT_ACCESS,
     },
     nvme_admin_opc,
     nvme_nvm_opcode,
@@ -165,6 +167,86 @@ impl NvmeDeviceHandle {
         // TODO: Optimize for ^2.
         (alignment == 0, offset_blocks, num_blocks)
     }
+
+    /// Submits a vectored write with the given NVMe IO flags
+    #[allow(clippy::too_many_arguments)]
+    fn submit_writev(
+        &self,
+        iov: *mut iovec,
+        iovcnt: i32,
+        offset_blocks: u64,
+        num_blocks: u64,
+        cb: IoCompletionCallback,
+        cb_arg: IoCompletionCallbackArg,
+        io_flags: u32,
+    ) -> Result<(), CoreError> {
+        check_io_args(IoType::Write, iov, iovcnt, offset_blocks, num_blocks)?;
+
+        let channel = self.io_channel.as_ptr();
+        let inner = NvmeIoChannel::inner_from_channel(channel);
+
+        // Make sure channel allows I/O.
+        check_channel_for_io(IoType::Write, inner, offset_blocks, num_blocks)?;
+
+        let bio = alloc_nvme_io_ctx(
+            IoType::Write,
+            NvmeIoCtx {
+                cb,
+                cb_arg,
+                iov,
+                iovcnt: iovcnt as u64,
+                iovpos: 0,
+                iov_offset: 0,
+                channel,
+                op: IoType::Write,
+                num_blocks,
+            },
+            offset_blocks,
+            num_blocks,
+        )?;
+
+        let rc;
+
+        if iovcnt == 1 {
+            rc = unsafe {
+                spdk_nvme_ns_cmd_write(
+                    self.ns.as_ptr(),
+                    inner.qpair.as_mut().unwrap().as_ptr(),
+                    (*iov).iov_base,
+                    offset_blocks,
+                    num_blocks as u32,
+                    Some(nvme_io_done),
+                    bio as *mut c_void,
+                    io_flags,
+                )
+            };
+        } else {
+            rc = unsafe {
+                spdk_nvme_ns_cmd_writev(
+                    self.ns.as_ptr(),
+                    inner.qpair.as_mut().unwrap().as_ptr(),
+                    offset_blocks,
+                    num_blocks as u32,
+                    Some(nvme_writev_done),
+                    bio as *mut c_void,
+                    io_flags,
+                    Some(nvme_queued_reset_sgl),
+                    Some(nvme_queued_next_sge),
+                )
+            }
+        }
+
+        if rc < 0 {
+            Err(CoreError::WriteDispatch {
+                source: Errno::from_i32(-rc),
+                offset: offset_blocks,
+                len: num_blocks,
+            })
+        } else {
+            inner.account_io();
+            Ok(())
+        }
+    }
 }
 
 extern "C" fn nvme_admin_passthru_done(
@@ -361,6 +443,11 @@ fn io_type_to_err(
             offset: offset_blocks,
             len: num_blocks,
         },
+        IoType::Flush => CoreError::FlushDispatch {
+            source,
+            offset: offset_blocks,
+            len: num_blocks,
+        },
         IoType::NvmeIo => CoreError::NvmeIoPassthruDispatch {
             source,
             opcode: 0xff,
@@ -686,72 +773,35 @@ impl BlockDeviceHandle for NvmeDeviceHandle {
         cb: IoCompletionCallback,
         cb_arg: IoCompletionCallbackArg,
     ) -> Result<(), CoreError> {
-        check_io_args(IoType::Write, iov, iovcnt, offset_blocks, num_blocks)?;
-
-        let channel = self.io_channel.as_ptr();
-        let inner = NvmeIoChannel::inner_from_channel(channel);
-
-        // Make sure channel allows I/O.
-        check_channel_for_io(IoType::Write, inner, offset_blocks, num_blocks)?;
-
-        let bio = alloc_nvme_io_ctx(
-            IoType::Write,
-            NvmeIoCtx {
-                cb,
-                cb_arg,
-                iov,
-                iovcnt: iovcnt as u64,
-                iovpos: 0,
-                iov_offset: 0,
-                channel,
-                op: IoType::Write,
-                num_blocks,
-            },
+        self.submit_writev(
+            iov,
+            iovcnt,
             offset_blocks,
             num_blocks,
-        )?;
-
-        let rc;
-
-        if iovcnt == 1 {
-            rc = unsafe {
-                spdk_nvme_ns_cmd_write(
-                    self.ns.as_ptr(),
-                    inner.qpair.as_mut().unwrap().as_ptr(),
-                    (*iov).iov_base,
-                    offset_blocks,
-                    num_blocks as u32,
-                    Some(nvme_io_done),
-                    bio as *mut c_void,
-                    self.prchk_flags,
-                )
-            };
-        } else {
-            rc = unsafe {
-                spdk_nvme_ns_cmd_writev(
-                    self.ns.as_ptr(),
-                    inner.qpair.as_mut().unwrap().as_ptr(),
-                    offset_blocks,
-                    num_blocks as u32,
-                    Some(nvme_writev_done),
-                    bio as *mut c_void,
-                    self.prchk_flags,
-                    Some(nvme_queued_reset_sgl),
-                    Some(nvme_queued_next_sge),
-                )
-            }
-        }
+            cb,
+            cb_arg,
+            self.prchk_flags,
+        )
+    }
 
-        if rc < 0 {
-            Err(CoreError::WriteDispatch {
-                source: Errno::from_i32(-rc),
-                offset: offset_blocks,
-                len: num_blocks,
-            })
-        } else {
-            inner.account_io();
-            Ok(())
-        }
+    fn writev_blocks_fua(
+        &self,
+        iov: *mut iovec,
+        iovcnt: i32,
+        offset_blocks: u64,
+        num_blocks: u64,
+        cb: IoCompletionCallback,
+        cb_arg: IoCompletionCallbackArg,
+    ) -> Result<(), CoreError> {
+        self.submit_writev(
+            iov,
+            iovcnt,
+            offset_blocks,
+            num_blocks,
+            cb,
+            cb_arg,
+            self.prchk_flags | SPDK_NVME_IO_FLAGS_FORCE_UNIT_ACCESS,
+        )
     }
 
     fn reset(
@@ -780,6 +830,58 @@ impl BlockDeviceHandle for NvmeDeviceHandle {
         )
     }
 
+    fn flush_io(
+        &self,
+        offset_blocks: u64,
+        num_blocks: u64,
+        cb: IoCompletionCallback,
+        cb_arg: IoCompletionCallbackArg,
+    ) -> Result<(), CoreError> {
+        let channel = self.io_channel.as_ptr();
+        let inner = NvmeIoChannel::inner_from_channel(channel);
+
+        // Make sure channel allows I/O
+        check_channel_for_io(IoType::Flush, inner, offset_blocks, num_blocks)?;
+
+        let bio = alloc_nvme_io_ctx(
+            IoType::Flush,
+            NvmeIoCtx {
+                cb,
+                cb_arg,
+                iov: std::ptr::null_mut() as *mut iovec, // No I/O vec involved.
+                iovcnt: 0,
+                iovpos: 0,
+                iov_offset: 0,
+                channel,
+                op: IoType::Flush,
+                num_blocks,
+            },
+            offset_blocks,
+            num_blocks,
+        )?;
+
+        // NVMe flushes the whole namespace, not just the given range
+        let rc = unsafe {
+            spdk_nvme_ns_cmd_flush(
+                self.ns.as_ptr(),
+                inner.qpair.as_mut().unwrap().as_ptr(),
+                Some(nvme_io_done),
+                bio as *mut c_void,
+            )
+        };
+
+        if rc < 0 {
+            Err(CoreError::FlushDispatch {
+                source: Errno::from_i32(-rc),
+                offset: offset_blocks,
+                len: num_blocks,
+            })
+        } else {
+            inner.account_io();
+            Ok(())
+        }
+    }
+
     fn unmap_blocks(
         &self,
         offset_blocks: u64,
@@ -4,6 +4,7 @@ use spdk_rs::libspdk::{
     spdk_nvme_ns,
     spdk_nvme_ns_get_extended_sector_size,
     spdk_nvme_ns_get_flags,
+    spdk_nvme_ns_get_max_io_xfer_size,
     spdk_nvme_ns_get_md_size,
     spdk_nvme_ns_get_num_sectors,
     spdk_nvme_ns_get_optimal_io_boundary,
@@ -65,6 +66,10 @@ impl NvmeNamespace {
         unsafe { spdk_nvme_ns_get_optimal_io_boundary(self.0.as_ptr()) as u64 }
     }
 
+    pub fn max_io_xfer_size(&self) -> u64 {
+        unsafe { spdk_nvme_ns_get_max_io_xfer_size(self.0.as_ptr()) as u64 }
+    }
+
     pub fn md_size(&self) -> u64 {
         unsafe { spdk_nvme_ns_get_md_size(self.0.as_ptr()) as u64 }
     }
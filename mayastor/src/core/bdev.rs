@@ -230,6 +230,28 @@ impl Bdev {
         self.0.set_num_blocks(count)
     }
 
+    /// returns true if the bdev has a volatile write cache, which needs to
+    /// be flushed for writes to be durable
+    pub fn has_write_cache(&self) -> bool {
+        unsafe { (*self.as_ptr()).write_cache != 0 }
+    }
+
+    /// set whether the bdev has a volatile write cache
+    /// # Safety
+    /// TODO
+    pub unsafe fn set_write_cache(&mut self, enabled: bool) {
+        (*self.as_ptr()).write_cache = enabled as i32;
+    }
+
+    /// have the bdev layer split IOs on the given boundary in blocks, or
+    /// not split them at all if there is none
+    /// # Safety
+    /// TODO
+    pub unsafe fn set_optimal_io_boundary(&mut self, boundary: Option<u32>) {
+        (*self.as_ptr()).optimal_io_boundary = boundary.unwrap_or(0);
+        (*self.as_ptr()).split_on_optimal_io_boundary = boundary.is_some();
+    }
+
     /// return the bdev size in bytes
     pub fn size_in_bytes(&self) -> u64 {
         self.0.size_in_bytes()
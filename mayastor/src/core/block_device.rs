@@ -1,6 +1,12 @@
 use super::{CoreError, DeviceEventSink, IoCompletionStatus, IoType};
 
-use spdk_rs::{DmaBuf, DmaError, IoVec};
+use spdk_rs::{
+    libspdk::{nvme_cmd_cdw10_get, spdk_nvme_cmd},
+    nvme_admin_opc,
+    DmaBuf,
+    DmaError,
+    IoVec,
+};
 
 use async_trait::async_trait;
 use merge::Merge;
@@ -53,6 +59,9 @@ pub trait BlockDevice {
     /// Returns aligment of the device.
     fn alignment(&self) -> u64;
 
+    /// Returns the largest I/O in bytes the device accepts, if it is limited.
+    fn max_io_size(&self) -> Option<u64>;
+
     /// Checks whether target I/O type is supported by the device.
     fn io_type_supported(&self, io_type: IoType) -> bool;
 
@@ -161,6 +170,18 @@ pub trait BlockDeviceHandle {
         cb_arg: IoCompletionCallbackArg,
     ) -> Result<(), CoreError>;
 
+    /// Write the given blocks like writev_blocks(), only completing once
+    /// they are on stable storage (Force Unit Access).
+    fn writev_blocks_fua(
+        &self,
+        iov: *mut IoVec,
+        iovcnt: i32,
+        offset_blocks: u64,
+        num_blocks: u64,
+        cb: IoCompletionCallback,
+        cb_arg: IoCompletionCallbackArg,
+    ) -> Result<(), CoreError>;
+
     /// TODO
     fn reset(
         &self,
@@ -168,6 +189,16 @@ pub trait BlockDeviceHandle {
         cb_arg: IoCompletionCallbackArg,
     ) -> Result<(), CoreError>;
 
+    /// Flush the given range of blocks out of any volatile write cache of
+    /// the device.
+    fn flush_io(
+        &self,
+        offset_blocks: u64,
+        num_blocks: u64,
+        cb: IoCompletionCallback,
+        cb_arg: IoCompletionCallbackArg,
+    ) -> Result<(), CoreError>;
+
     /// TODO
     fn unmap_blocks(
         &self,
@@ -201,6 +232,22 @@ pub trait BlockDeviceHandle {
     /// TODO
     async fn nvme_identify_ctrlr(&self) -> Result<DmaBuf, CoreError>;
 
+    /// Read the NVMe log page `lid` of the controller into the buffer,
+    /// which determines the number of bytes transferred.
+    async fn nvme_get_log_page(
+        &self,
+        lid: u8,
+        buffer: &mut DmaBuf,
+    ) -> Result<(), CoreError> {
+        let mut cmd = spdk_nvme_cmd::default();
+        cmd.set_opc(nvme_admin_opc::GET_LOG_PAGE.into());
+        cmd.nsid = 0xffffffff;
+        // number of dwords to read, zero based
+        let numdl = (buffer.len() as u32 / 4).saturating_sub(1) & 0xfff;
+        unsafe { *nvme_cmd_cdw10_get(&mut cmd) = numdl << 16 | lid as u32 };
+        self.nvme_admin(&cmd, Some(buffer)).await
+    }
+
     /// TODO
     async fn create_snapshot(&self) -> Result<u64, CoreError>;
 
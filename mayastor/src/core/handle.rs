@@ -11,11 +11,13 @@ use nix::errno::Errno;
 use spdk_rs::{
     libspdk::{
         spdk_bdev_desc,
+        spdk_bdev_flush,
         spdk_bdev_free_io,
         spdk_bdev_io,
         spdk_bdev_nvme_admin_passthru_ro,
         spdk_bdev_read,
         spdk_bdev_reset,
+        spdk_bdev_unmap,
         spdk_bdev_write,
         spdk_bdev_write_zeroes,
         spdk_io_channel,
@@ -212,6 +214,36 @@ impl BdevHandle {
         }
     }
 
+    /// flush the whole bdev out of any volatile write cache
+    pub async fn flush(&self) -> Result<(), CoreError> {
+        let len = self.get_bdev().size_in_bytes();
+        let (s, r) = oneshot::channel::<bool>();
+        let errno = unsafe {
+            spdk_bdev_flush(
+                self.desc.as_ptr(),
+                self.channel.as_ptr(),
+                0,
+                len,
+                Some(Self::io_completion_cb),
+                cb_arg(s),
+            )
+        };
+
+        if errno != 0 {
+            return Err(CoreError::FlushDispatch {
+                source: Errno::from_i32(errno.abs()),
+                offset: 0,
+                len,
+            });
+        }
+
+        if r.await.expect("Failed awaiting flush IO") {
+            Ok(())
+        } else {
+            Err(CoreError::FlushFailed {})
+        }
+    }
+
     pub async fn write_zeroes_at(
         &self,
         offset: u64,
@@ -247,6 +279,42 @@ impl BdevHandle {
         }
     }
 
+    /// unmap (trim) the given byte range of the bdev
+    pub async fn unmap_at(
+        &self,
+        offset: u64,
+        len: u64,
+    ) -> Result<(), CoreError> {
+        let (s, r) = oneshot::channel::<bool>();
+        let errno = unsafe {
+            spdk_bdev_unmap(
+                self.desc.as_ptr(),
+                self.channel.as_ptr(),
+                offset,
+                len,
+                Some(Self::io_completion_cb),
+                cb_arg(s),
+            )
+        };
+
+        if errno != 0 {
+            return Err(CoreError::UnmapDispatch {
+                source: Errno::from_i32(errno.abs()),
+                offset,
+                len,
+            });
+        }
+
+        if r.await.expect("Failed awaiting unmap IO") {
+            Ok(())
+        } else {
+            Err(CoreError::UnmapFailed {
+                offset,
+                len,
+            })
+        }
+    }
+
     /// create a snapshot, only works for nvme bdev
     /// returns snapshot time as u64 seconds since Unix epoch
     pub async fn create_snapshot(&self) -> Result<u64, CoreError> {
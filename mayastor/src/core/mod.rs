@@ -115,6 +115,16 @@ pub enum CoreError {
     ResetDispatch {
         source: Errno,
     },
+    #[snafu(display(
+        "Failed to dispatch flush at offset {} length {}",
+        offset,
+        len
+    ))]
+    FlushDispatch {
+        source: Errno,
+        offset: u64,
+        len: u64,
+    },
     #[snafu(display(
         "Failed to dispatch NVMe Admin command {:x}h: {}",
         opcode,
@@ -165,6 +175,8 @@ pub enum CoreError {
     },
     #[snafu(display("Reset failed"))]
     ResetFailed {},
+    #[snafu(display("Flush failed"))]
+    FlushFailed {},
     #[snafu(display(
         "Write zeroes failed at offset {} length {}",
         offset,
@@ -174,6 +186,11 @@ pub enum CoreError {
         offset: u64,
         len: u64,
     },
+    #[snafu(display("Unmap failed at offset {} length {}", offset, len))]
+    UnmapFailed {
+        offset: u64,
+        len: u64,
+    },
     #[snafu(display("NVMe Admin command {:x}h failed", opcode))]
     NvmeAdminFailed {
         opcode: u16,
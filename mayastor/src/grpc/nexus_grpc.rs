@@ -30,6 +30,7 @@ impl From<ChildState> for rpc::ChildState {
             ChildState::Open => rpc::ChildState::ChildOnline,
             ChildState::Destroying => rpc::ChildState::ChildDegraded,
             ChildState::Closed => rpc::ChildState::ChildDegraded,
+            ChildState::Offline => rpc::ChildState::ChildDegraded,
             ChildState::Faulted(reason) => match reason {
                 Reason::OutOfSync => rpc::ChildState::ChildDegraded,
                 _ => rpc::ChildState::ChildFaulted,
@@ -38,6 +38,9 @@ pub enum Error {
     #[snafu(display("failed to destroy lvol {}", name))]
     RepDestroy { source: Errno, name: String },
 
+    #[snafu(display("errno: {} failed to resize lvol {}", source, name))]
+    RepResize { source: Errno, name: String },
+
     #[snafu(display("bdev {} is not a lvol", name))]
     NotALvol { source: Errno, name: String },
 
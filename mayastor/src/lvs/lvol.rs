@@ -24,6 +24,7 @@ use spdk_rs::libspdk::{
     vbdev_lvol_create_snapshot,
     vbdev_lvol_destroy,
     vbdev_lvol_get_from_bdev,
+    vbdev_lvol_resize,
     LVS_CLEAR_WITH_UNMAP,
     SPDK_BDEV_LARGE_BUF_MAX_SIZE,
 };
@@ -317,6 +318,31 @@ impl Lvol {
         Ok(name)
     }
 
+    /// resize the lvol to the given size in bytes, which is rounded up to
+    /// the cluster size of the pool
+    pub async fn resize(&self, size: u64) -> Result<(), Error> {
+        extern "C" fn resize_cb(sender: *mut c_void, errno: i32) {
+            let sender =
+                unsafe { Box::from_raw(sender as *mut oneshot::Sender<i32>) };
+            sender.send(errno).unwrap();
+        }
+
+        let (s, r) = pair::<i32>();
+        unsafe {
+            vbdev_lvol_resize(self.0.as_ptr(), size, Some(resize_cb), cb_arg(s))
+        };
+
+        r.await
+            .expect("lvol resize callback is gone")
+            .to_result(|e| Error::RepResize {
+                source: Errno::from_i32(e),
+                name: self.name(),
+            })?;
+
+        info!("Resized {} to {} bytes", self.name(), self.size());
+        Ok(())
+    }
+
     /// callback executed after synchronizing the lvols metadata
     extern "C" fn blob_sync_cb(sender_ptr: *mut c_void, errno: i32) {
         let sender =
@@ -2,7 +2,10 @@
 mod rebuild_api;
 /// Rebuild implementation module
 pub mod rebuild_impl;
+/// Map of the regions to rebuild
+mod rebuild_map;
 
 pub use rebuild_api::*;
+pub use rebuild_map::RebuildMap;
 // for the tests only
 pub use rebuild_impl::SEGMENT_SIZE;
@@ -1,6 +1,6 @@
 #![warn(missing_docs)]
 
-use std::fmt;
+use std::{fmt, sync::Arc};
 
 use crossbeam::channel::{Receiver, Sender};
 use futures::channel::oneshot;
@@ -13,7 +13,7 @@ use crate::{
 };
 use spdk_rs::DmaError;
 
-use super::rebuild_impl::*;
+use super::{rebuild_impl::*, RebuildMap};
 
 #[derive(Debug, Snafu, Clone)]
 #[snafu(visibility = "pub(crate)")]
@@ -132,6 +132,12 @@ pub struct RebuildJob {
     pub(super) complete_chan: Vec<oneshot::Sender<RebuildState>>,
     /// rebuild copy error, if any
     pub error: Option<RebuildError>,
+    /// time at which the job was created
+    pub start_time: chrono::DateTime<chrono::Utc>,
+    /// regions to copy, relative to the start of the range; everything is
+    /// copied when there is no map. The nexus keeps marking it until the
+    /// destination receives all writes.
+    pub(super) map: Option<Arc<parking_lot::Mutex<RebuildMap>>>,
 
     // Pre-opened descriptors for source/destination block device.
     pub(super) src_descriptor: Box<dyn BlockDeviceDescriptor>,
@@ -157,6 +163,8 @@ pub struct RebuildStats {
     pub blocks_total: u64,
     /// number of blocks recovered
     pub blocks_recovered: u64,
+    /// number of blocks which did not need to be copied
+    pub blocks_skipped: u64,
     /// rebuild progress in %
     pub progress: u64,
     /// granularity of each recovery copy in blocks
@@ -196,15 +204,18 @@ impl RebuildJob {
     /// Creates a new RebuildJob which rebuilds from source URI to target URI
     /// from start to end (of the data partition); notify_fn callback is called
     /// when the rebuild state is updated - with the nexus and destination
-    /// URI as arguments
+    /// URI as arguments. When a map is given only its dirty regions are
+    /// copied.
     pub fn create<'a>(
         nexus: &str,
         source: &str,
         destination: &'a str,
         range: std::ops::Range<u64>,
+        map: Option<Arc<parking_lot::Mutex<RebuildMap>>>,
         notify_fn: fn(String, String) -> (),
     ) -> Result<&'a mut Self, RebuildError> {
-        Self::new(nexus, source, destination, range, notify_fn)?.store()?;
+        Self::new(nexus, source, destination, range, map, notify_fn)?
+            .store()?;
 
         Self::lookup(destination)
     }
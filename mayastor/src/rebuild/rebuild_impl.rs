@@ -1,6 +1,6 @@
 #![warn(missing_docs)]
 
-use std::{cell::UnsafeCell, collections::HashMap};
+use std::{cell::UnsafeCell, collections::HashMap, sync::Arc};
 
 use crossbeam::channel::unbounded;
 use futures::{
@@ -28,7 +28,7 @@ use crate::{
     nexus_uri::bdev_get_name,
 };
 
-use super::rebuild_api::*;
+use super::{rebuild_api::*, RebuildMap};
 
 /// Global list of rebuild jobs using a static OnceCell
 pub(super) struct RebuildInstances {
@@ -77,6 +77,8 @@ pub(super) struct RebuildTasks {
     total: usize,
 
     segments_done: u64,
+    /// blocks left alone as the rebuild map has them clean
+    blocks_skipped: u64,
 }
 
 /// Checks whether a range is contained within another range
@@ -118,6 +120,7 @@ impl RebuildJob {
         source: &str,
         destination: &str,
         range: std::ops::Range<u64>,
+        map: Option<Arc<parking_lot::Mutex<RebuildMap>>>,
         notify_fn: fn(String, String) -> (),
     ) -> Result<Self, RebuildError> {
         let src_descriptor = device_open(
@@ -165,6 +168,7 @@ impl RebuildJob {
             active: 0,
             total: SEGMENT_TASKS,
             segments_done: 0,
+            blocks_skipped: 0,
         };
 
         for _ in 0 .. tasks.total {
@@ -204,6 +208,8 @@ impl RebuildJob {
             states: Default::default(),
             complete_chan: Vec::new(),
             error: None,
+            start_time: chrono::Utc::now(),
+            map,
             src_descriptor,
             dst_descriptor,
         })
@@ -214,6 +220,10 @@ impl RebuildJob {
     // until the bdev is fully rebuilt
     async fn run(&mut self) {
         self.start_all_tasks();
+        if self.task_pool.active == 0 {
+            // nothing was dirty so there is nothing to copy
+            self.complete();
+        }
         while self.task_pool.active > 0 {
             match self.await_one_task().await {
                 Some(r) => match r.error {
@@ -497,16 +507,19 @@ impl ClientOperations for RebuildJob {
         let blocks_total = self.range.end - self.range.start;
 
         // segment size may not be aligned to the total size
+        let blocks_skipped = self.task_pool.blocks_skipped;
         let blocks_recovered = std::cmp::min(
             self.task_pool.segments_done * self.segment_size_blks,
-            blocks_total,
+            blocks_total - blocks_skipped,
         );
 
-        let progress = (blocks_recovered * 100) / blocks_total;
+        let progress =
+            ((blocks_recovered + blocks_skipped) * 100) / blocks_total;
 
         info!(
             "State: {}, Src: {}, Dst: {}, range: {:?}, next: {}, \
-             block_size: {}, segment_sz: {}, recovered_blks: {}, progress: {}%",
+             block_size: {}, segment_sz: {}, recovered_blks: {}, \
+             skipped_blks: {}, progress: {}%",
             self.state(),
             self.source,
             self.destination,
@@ -515,12 +528,14 @@ impl ClientOperations for RebuildJob {
             self.block_size,
             self.segment_size_blks,
             blocks_recovered,
+            blocks_skipped,
             progress,
         );
 
         RebuildStats {
             blocks_total,
             blocks_recovered,
+            blocks_skipped,
             progress,
             segment_size_blks: self.segment_size_blks,
             block_size: self.block_size,
@@ -577,6 +592,22 @@ impl InternalOperations for RebuildJob {
 }
 
 impl RebuildJob {
+    /// Advances past the segments which the rebuild map says are clean,
+    /// counting them as skipped rather than recovered.
+    fn skip_clean_segments(&mut self) {
+        if let Some(map) = self.map.clone() {
+            let map = map.lock();
+            while self.next < self.range.end {
+                let blks = self.get_segment_size_blks(self.next);
+                if map.is_dirty(self.next - self.range.start, blks) {
+                    break;
+                }
+                self.next += blks;
+                self.task_pool.blocks_skipped += blks;
+            }
+        }
+    }
+
     fn start_all_tasks(&mut self) {
         assert_eq!(
             self.task_pool.active, 0,
@@ -642,7 +673,8 @@ impl RebuildJob {
 
     /// Sends one segment worth of data in a reactor future and notifies the
     /// management channel. Returns the next segment offset to rebuild, if any
-    fn send_segment_task(&self, id: usize) -> Option<u64> {
+    fn send_segment_task(&mut self, id: usize) -> Option<u64> {
+        self.skip_clean_segments();
         if self.next >= self.range.end {
             None
         } else {
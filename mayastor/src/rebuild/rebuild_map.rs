@@ -0,0 +1,70 @@
+//! Coarse bitmap of the regions of a device which need to be rebuilt.
+//! The nexus records the regions written while one of its children is
+//! offline so that bringing the child back online only copies those.
+
+use serde::{Deserialize, Serialize};
+
+/// size of a tracked region in bytes
+const REGION_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Bitmap of dirty regions, addressed by block offset from the start of the
+/// data partition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebuildMap {
+    /// size of a region in blocks
+    region_blks: u64,
+    /// one bit per region, set if the region was written to
+    bits: Vec<u64>,
+}
+
+impl RebuildMap {
+    /// Creates an empty map covering `num_blocks` blocks of `block_len`
+    /// bytes each.
+    pub fn new(num_blocks: u64, block_len: u64) -> Self {
+        let region_blks = std::cmp::max(REGION_SIZE / block_len, 1);
+        let regions = (num_blocks + region_blks - 1) / region_blks;
+        Self {
+            region_blks,
+            bits: vec![0; ((regions + 63) / 64) as usize],
+        }
+    }
+
+    /// Marks the regions covering the given block range as dirty.
+    pub fn mark(&mut self, offset: u64, num_blocks: u64) {
+        for region in self.regions(offset, num_blocks) {
+            if let Some(word) = self.bits.get_mut((region / 64) as usize) {
+                *word |= 1 << (region % 64);
+            }
+        }
+    }
+
+    /// Returns true if any region covering the given block range is dirty.
+    pub fn is_dirty(&self, offset: u64, num_blocks: u64) -> bool {
+        self.regions(offset, num_blocks).any(|region| {
+            self.bits
+                .get((region / 64) as usize)
+                .map_or(false, |word| word & (1 << (region % 64)) != 0)
+        })
+    }
+
+    /// Returns true if the map covers a device of `num_blocks` blocks of
+    /// `block_len` bytes each, as one created for it would.
+    pub fn covers(&self, num_blocks: u64, block_len: u64) -> bool {
+        let map = Self::new(num_blocks, block_len);
+        map.region_blks == self.region_blks && map.bits.len() == self.bits.len()
+    }
+
+    /// Returns the number of dirty regions.
+    pub fn count_dirty(&self) -> u64 {
+        self.bits.iter().map(|word| word.count_ones() as u64).sum()
+    }
+
+    /// Range of the regions covering the given block range.
+    fn regions(&self, offset: u64, num_blocks: u64) -> std::ops::Range<u64> {
+        if num_blocks == 0 {
+            return 0 .. 0;
+        }
+        offset / self.region_blks
+            .. (offset + num_blocks - 1) / self.region_blks + 1
+    }
+}
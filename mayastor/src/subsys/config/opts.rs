@@ -52,6 +52,15 @@ pub struct NexusOpts {
     pub iscsi_nexus_port: u16,
     /// Port for replica target portal
     pub iscsi_replica_port: u16,
+    /// time in milliseconds to wait for children which are not present yet
+    /// when creating a nexus, 0 to fail right away
+    pub child_wait_timeout_ms: u64,
+    /// attempts to reconnect a remote nvmf child which faulted because of
+    /// an IO error, 0 to leave it faulted
+    pub child_reconnect_attempts: u32,
+    /// time in milliseconds before the first reconnect attempt, doubled on
+    /// every further attempt
+    pub child_reconnect_delay_ms: u64,
 }
 
 /// Default nvmf port used for replicas.
@@ -74,6 +83,9 @@ impl Default for NexusOpts {
             iscsi_enable: true,
             iscsi_nexus_port: ISCSI_PORT_NEXUS,
             iscsi_replica_port: ISCSI_PORT_REPLICA,
+            child_wait_timeout_ms: 0,
+            child_reconnect_attempts: 0,
+            child_reconnect_delay_ms: 1000,
         }
     }
 }
@@ -75,6 +75,17 @@ pub enum Error {
     Namespace { bdev: String, msg: String },
     #[snafu(display("Failed to find listener for {} {}", nqn, trid))]
     Listener { nqn: String, trid: String },
+    #[snafu(display(
+        "Cannot export {} over {} paths, {} transport addresses available",
+        nqn,
+        num_paths,
+        available
+    ))]
+    Paths {
+        nqn: String,
+        num_paths: usize,
+        available: usize,
+    },
 }
 
 thread_local! {
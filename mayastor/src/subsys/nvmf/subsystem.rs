@@ -300,22 +300,25 @@ impl NvmfSubsystem {
 
     // we currently allow all listeners to the subsystem
     async fn add_listener(&self) -> Result<(), Error> {
+        // dont yet enable both ports, IOW just add one transportID now
+        self.add_listener_port(Config::get().nexus_opts.nvmf_replica_port)
+            .await
+    }
+
+    /// add a listener on the given port of the target
+    async fn add_listener_port(&self, port: u16) -> Result<(), Error> {
         extern "C" fn listen_cb(arg: *mut c_void, status: i32) {
             let s = unsafe { Box::from_raw(arg as *mut oneshot::Sender<i32>) };
             s.send(status).unwrap();
         }
 
-        let cfg = Config::get();
-
-        // dont yet enable both ports, IOW just add one transportID now
-
-        let trid_replica = TransportId::new(cfg.nexus_opts.nvmf_replica_port);
+        let trid = TransportId::new(port);
 
         let (s, r) = oneshot::channel::<i32>();
         unsafe {
             spdk_nvmf_subsystem_add_listener(
                 self.0.as_ptr(),
-                trid_replica.as_ptr(),
+                trid.as_ptr(),
                 Some(listen_cb),
                 cb_arg(s),
             );
@@ -329,6 +332,50 @@ impl NvmfSubsystem {
         })
     }
 
+    /// make the started subsystem reachable over `num_paths` paths, one per
+    /// nvmf port the target listens on, so that initiators can use multipath
+    pub async fn set_num_paths(&self, num_paths: usize) -> Result<(), Error> {
+        let cfg = Config::get();
+        let mut ports = vec![cfg.nexus_opts.nvmf_replica_port];
+        if cfg.nexus_opts.nvmf_nexus_port != cfg.nexus_opts.nvmf_replica_port {
+            ports.push(cfg.nexus_opts.nvmf_nexus_port);
+        }
+
+        if num_paths == 0 || num_paths > ports.len() {
+            return Err(Error::Paths {
+                nqn: self.get_nqn(),
+                num_paths,
+                available: ports.len(),
+            });
+        }
+
+        let listening = self
+            .listeners_to_vec()
+            .unwrap_or_default()
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>();
+        let missing = ports[.. num_paths]
+            .iter()
+            .filter(|p| !listening.contains(&TransportId::new(**p).to_string()))
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        // listeners can only be added while the subsystem is paused
+        self.pause().await?;
+        let mut result = Ok(());
+        for port in missing {
+            result = self.add_listener_port(*port).await;
+            if result.is_err() {
+                break;
+            }
+        }
+        self.resume().await?;
+        result
+    }
+
     /// start the subsystem previously created -- note that we destroy it on
     /// failure to ensure the state is not in limbo and to avoid leaking
     /// resources
@@ -53,3 +53,14 @@ pub async fn write_zeroes_some(
     h.write_zeroes_at(offset, len).await?;
     Ok(())
 }
+
+pub async fn unmap_some(
+    nexus_name: &str,
+    offset: u64,
+    len: u64,
+) -> Result<(), CoreError> {
+    let h = BdevHandle::open(nexus_name, true, false)?;
+
+    h.unmap_at(offset, len).await?;
+    Ok(())
+}
@@ -3,7 +3,11 @@ use spdk_rs::libspdk::{
     vbdev_error_create,
     vbdev_error_inject_error,
 };
-pub use spdk_rs::libspdk::{SPDK_BDEV_IO_TYPE_READ, SPDK_BDEV_IO_TYPE_WRITE};
+pub use spdk_rs::libspdk::{
+    SPDK_BDEV_IO_TYPE_FLUSH,
+    SPDK_BDEV_IO_TYPE_READ,
+    SPDK_BDEV_IO_TYPE_WRITE,
+};
 
 // constant used by the vbdev_error module but not exported
 pub const VBDEV_IO_FAILURE: u32 = 1;
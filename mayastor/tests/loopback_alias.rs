@@ -0,0 +1,49 @@
+use mayastor::{
+    bdev::{
+        list_aliases,
+        nexus::{nexus_create, nexus_lookup_mut},
+        prune_aliases,
+    },
+    core::MayastorCliArgs,
+    nexus_uri::{bdev_create, bdev_destroy},
+};
+
+pub mod common;
+use common::MayastorTest;
+
+static NEXUS_NAME: &str = "loopback_alias";
+static CHILD_URI: &str = "bdev:///malloc0";
+static LOOPBACK_URI: &str = "loopback:///malloc0";
+
+#[tokio::test]
+async fn loopback_alias_prune() {
+    let ms = MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        bdev_create("malloc:///malloc0?size_mb=64").await.unwrap();
+
+        bdev_create(LOOPBACK_URI).await.unwrap();
+        nexus_create(NEXUS_NAME, 32 * 1024 * 1024, None, &[CHILD_URI.into()])
+            .await
+            .unwrap();
+
+        let mut aliases = list_aliases("malloc0").unwrap();
+        aliases.sort();
+        assert_eq!(aliases, vec![CHILD_URI.to_string(), LOOPBACK_URI.into()]);
+
+        // the aliases of a live loopback and of a nexus child are kept
+        assert!(prune_aliases("malloc0").unwrap().is_empty());
+        assert_eq!(list_aliases("malloc0").unwrap().len(), 2);
+        assert!(list_aliases("malloc1").is_err());
+
+        bdev_destroy(LOOPBACK_URI).await.unwrap();
+        assert_eq!(list_aliases("malloc0").unwrap(), vec![CHILD_URI]);
+
+        nexus_lookup_mut(NEXUS_NAME)
+            .unwrap()
+            .destroy()
+            .await
+            .unwrap();
+        bdev_destroy("malloc:///malloc0").await.unwrap();
+    })
+    .await;
+}
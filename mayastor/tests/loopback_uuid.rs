@@ -0,0 +1,47 @@
+use mayastor::{
+    core::{Bdev, MayastorCliArgs},
+    nexus_uri::{bdev_create, bdev_destroy, NexusBdevError},
+};
+
+pub mod common;
+use common::MayastorTest;
+
+static MALLOC_UUID: &str = "bd2f9b0c-2a0d-4b7c-9c5f-7f3b0f2a6e01";
+static STALE_UUID: &str = "5b1d5ab1-7cbe-4a5e-a2f2-34b1e3a2c9d7";
+
+#[tokio::test]
+async fn loopback_uuid() {
+    let ms = MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        bdev_create(&format!(
+            "malloc:///malloc0?size_mb=64&uuid={}",
+            MALLOC_UUID
+        ))
+        .await
+        .unwrap();
+
+        // a uuid mismatch is rejected by default
+        assert!(matches!(
+            bdev_create(&format!("loopback:///malloc0?uuid={}", STALE_UUID))
+                .await,
+            Err(NexusBdevError::BdevWrongUuid { .. })
+        ));
+        assert_eq!(
+            Bdev::lookup_by_name("malloc0").unwrap().uuid_as_string(),
+            MALLOC_UUID
+        );
+
+        // unless the device is explicitly accepted, which keeps its uuid
+        let uri =
+            format!("loopback:///malloc0?uuid={}&force_uuid=true", STALE_UUID);
+        assert_eq!(bdev_create(&uri).await.unwrap(), "malloc0");
+        assert_eq!(
+            Bdev::lookup_by_name("malloc0").unwrap().uuid_as_string(),
+            MALLOC_UUID
+        );
+
+        bdev_destroy(&uri).await.unwrap();
+        bdev_destroy("malloc:///malloc0").await.unwrap();
+    })
+    .await;
+}
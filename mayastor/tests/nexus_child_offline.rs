@@ -0,0 +1,137 @@
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    time::Duration,
+};
+
+use mayastor::{
+    bdev::nexus::{nexus_create, nexus_lookup_mut, ChildState, NexusStatus},
+    core::MayastorCliArgs,
+    rebuild::RebuildJob,
+};
+
+pub mod common;
+use common::{bdev_io, MayastorTest};
+
+static NXNAME: &str = "offline_nexus";
+static NEXUS_SIZE: u64 = 32 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/offline-disk1.img";
+static BDEVNAME1: &str = "aio:///tmp/offline-disk1.img?blk_size=512";
+static DISKNAME2: &str = "/tmp/offline-disk2.img";
+static BDEVNAME2: &str = "aio:///tmp/offline-disk2.img?blk_size=512";
+
+static OFFLINE_OFFSET: u64 = 4 * 1024 * 1024;
+
+/// Reads the first byte of the nexus block at `offset` straight from the
+/// backing file of a child.
+fn child_byte(disk: &str, data_offset: u64, offset: u64) -> u8 {
+    let mut file = File::open(disk).unwrap();
+    file.seek(SeekFrom::Start(data_offset + offset)).unwrap();
+    let mut buf = [0u8; 1];
+    file.read_exact(&mut buf).unwrap();
+    buf[0]
+}
+
+fn child_state(name: &str) -> ChildState {
+    nexus_lookup_mut(NXNAME)
+        .unwrap()
+        .children
+        .iter()
+        .find(|c| c.name == name)
+        .unwrap()
+        .state()
+}
+
+#[tokio::test]
+async fn nexus_child_offline() {
+    common::delete_file(&[DISKNAME1.into(), DISKNAME2.into()]);
+    common::truncate_file(DISKNAME1, 64 * 1024);
+    common::truncate_file(DISKNAME2, 64 * 1024);
+
+    let mayastor = MayastorTest::new(MayastorCliArgs::default());
+    let data_offset = mayastor
+        .spawn(async {
+            nexus_create(
+                NXNAME,
+                NEXUS_SIZE,
+                None,
+                &[BDEVNAME1.to_string(), BDEVNAME2.to_string()],
+            )
+            .await
+            .unwrap();
+
+            let mut nexus = nexus_lookup_mut(NXNAME).unwrap();
+
+            // only an offline or closed child can be brought online
+            assert!(nexus.as_mut().online_child(BDEVNAME2).await.is_err());
+
+            let status = nexus.as_mut().offline_child(BDEVNAME2).await.unwrap();
+            assert_eq!(status, NexusStatus::Degraded);
+            assert_eq!(child_state(BDEVNAME2), ChildState::Offline);
+
+            let last = nexus.children[1].state_history().pop().unwrap();
+            assert_eq!(last.from, ChildState::Open);
+            assert_eq!(last.to, ChildState::Offline);
+
+            // IO carries on without the offline child
+            bdev_io::write_some(NXNAME, OFFLINE_OFFSET, 0xaa)
+                .await
+                .unwrap();
+            bdev_io::read_some(NXNAME, OFFLINE_OFFSET, 0xaa)
+                .await
+                .unwrap();
+            assert_eq!(child_state(BDEVNAME2), ChildState::Offline);
+
+            nexus.data_ent_offset * nexus.block_len()
+        })
+        .await;
+
+    assert_eq!(child_byte(DISKNAME1, data_offset, OFFLINE_OFFSET), 0xaa);
+    assert_eq!(child_byte(DISKNAME2, data_offset, OFFLINE_OFFSET), 0);
+
+    // bringing it back online starts its rebuild
+    mayastor
+        .spawn(async {
+            let nexus = nexus_lookup_mut(NXNAME).unwrap();
+            nexus.online_child(BDEVNAME2).await.unwrap();
+            assert!(
+                RebuildJob::lookup(BDEVNAME2).is_ok()
+                    || !nexus_lookup_mut(NXNAME)
+                        .unwrap()
+                        .rebuild_history()
+                        .is_empty()
+            );
+            assert_ne!(child_state(BDEVNAME2), ChildState::Offline);
+        })
+        .await;
+
+    let mut ticker = tokio::time::interval(Duration::from_millis(100));
+    for _ in 0 .. 100 {
+        ticker.tick().await;
+        let open = mayastor
+            .spawn(async { child_state(BDEVNAME2) == ChildState::Open })
+            .await;
+        if open {
+            break;
+        }
+    }
+
+    mayastor
+        .spawn(async {
+            let nexus = nexus_lookup_mut(NXNAME).unwrap();
+            assert_eq!(child_state(BDEVNAME2), ChildState::Open);
+            assert_eq!(nexus.status(), NexusStatus::Online);
+
+            let history = nexus.rebuild_history();
+            assert_eq!(history.len(), 1);
+            assert_eq!(history[0].child, BDEVNAME2);
+            assert_eq!(history[0].state, "completed");
+
+            nexus.destroy().await.unwrap();
+        })
+        .await;
+
+    assert_eq!(child_byte(DISKNAME2, data_offset, OFFLINE_OFFSET), 0xaa);
+    common::delete_file(&[DISKNAME1.into(), DISKNAME2.into()]);
+}
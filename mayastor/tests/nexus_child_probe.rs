@@ -0,0 +1,91 @@
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+};
+
+use mayastor::{
+    bdev::nexus::{nexus_create, nexus_lookup_mut, Error},
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+use common::{
+    error_bdev::{
+        create_error_bdev,
+        inject_error,
+        SPDK_BDEV_IO_TYPE_WRITE,
+        VBDEV_IO_FAILURE,
+    },
+    MayastorTest,
+};
+
+static NEXUS_NAME: &str = "ProbeNexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+
+static DISKNAME: &str = "/tmp/probe-disk.img";
+static ERROR_DEVICE: &str = "probe_error_device";
+static EE_ERROR_DEVICE: &str = "EE_probe_error_device";
+static BAD_CHILD: &str = "bdev:///EE_probe_error_device";
+static GOOD_CHILD: &str = "malloc:///malloc0?blk_size=512&size_mb=12";
+
+/// Reads a block straight from the backing file of the bad child
+fn disk_block(offset: u64, len: u64) -> Vec<u8> {
+    let mut file = File::open(DISKNAME).unwrap();
+    file.seek(SeekFrom::Start(offset)).unwrap();
+    let mut buf = vec![0u8; len as usize];
+    file.read_exact(&mut buf).unwrap();
+    buf
+}
+
+#[tokio::test]
+async fn nexus_child_probe() {
+    common::delete_file(&[DISKNAME.into()]);
+    common::truncate_file(DISKNAME, 64 * 1024);
+
+    let ms = MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        create_error_bdev(ERROR_DEVICE, DISKNAME);
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[BAD_CHILD.to_string(), GOOD_CHILD.to_string()],
+        )
+        .await
+        .unwrap();
+
+        let nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+        let block_len = nexus.block_len();
+        let offset = (nexus.data_ent_offset - 1) * block_len;
+        let original = disk_block(offset, block_len);
+
+        nexus.probe_child(GOOD_CHILD).await.unwrap();
+        nexus.probe_child(BAD_CHILD).await.unwrap();
+        // the probed block is put back as it was
+        assert_eq!(disk_block(offset, block_len), original);
+
+        assert!(matches!(
+            nexus.probe_child("malloc:///nope").await,
+            Err(Error::ChildNotFound { .. })
+        ));
+
+        // a child which cannot be written to fails the probe
+        inject_error(
+            EE_ERROR_DEVICE,
+            SPDK_BDEV_IO_TYPE_WRITE,
+            VBDEV_IO_FAILURE,
+            1,
+        );
+        assert!(matches!(
+            nexus.probe_child(BAD_CHILD).await,
+            Err(Error::ChildProbe { .. })
+        ));
+        // also when the probe fails
+        assert_eq!(disk_block(offset, block_len), original);
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME.into()]);
+}
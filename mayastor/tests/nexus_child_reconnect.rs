@@ -0,0 +1,157 @@
+use common::{bdev_io, compose::Builder, MayastorTest};
+use mayastor::{
+    bdev::nexus::{
+        nexus_create,
+        nexus_lookup_mut,
+        reconnect_delays,
+        reconnect_wanted,
+        ChildState,
+        Reason,
+    },
+    core::MayastorCliArgs,
+    subsys::{Config, NexusOpts, NvmeBdevOpts},
+};
+use rpc::mayastor::{BdevShareRequest, BdevUri};
+use tokio::time::Duration;
+
+pub mod common;
+static NXNAME: &str = "reconnect_nexus";
+static LOCAL_CHILD: &str = "malloc:///malloc0?size_mb=64";
+
+#[test]
+fn nexus_child_reconnect_attempts() {
+    // the delay doubles after every attempt, up to a limit
+    let delays = reconnect_delays(8, Duration::from_secs(1))
+        .iter()
+        .map(|d| d.as_secs())
+        .collect::<Vec<_>>();
+    assert_eq!(delays, vec![1, 2, 4, 8, 16, 30, 30, 30]);
+    assert!(reconnect_delays(0, Duration::from_secs(1)).is_empty());
+
+    // only a child which is still faulted by an IO error is reconnected,
+    // the attempts stop once it is removed or back some other way
+    assert!(reconnect_wanted(Some(ChildState::Faulted(Reason::IoError))));
+    assert!(!reconnect_wanted(Some(ChildState::Destroying)));
+    assert!(!reconnect_wanted(Some(ChildState::Open)));
+    assert!(!reconnect_wanted(None));
+}
+
+#[tokio::test]
+async fn nexus_child_reconnect() {
+    // Use shorter timeouts than the defaults to reduce test runtime
+    Config::get_or_init(|| Config {
+        nvme_bdev_opts: NvmeBdevOpts {
+            timeout_us: 2_000_000,
+            keep_alive_timeout_ms: 2_000,
+            retry_count: 1,
+            ..Default::default()
+        },
+        nexus_opts: NexusOpts {
+            child_reconnect_attempts: 10,
+            child_reconnect_delay_ms: 1_000,
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .apply();
+    let test = Builder::new()
+        .name("cargo-test")
+        .network("10.1.0.0/16")
+        .add_container("ms1")
+        .with_clean(true)
+        .build()
+        .await
+        .unwrap();
+
+    let mut hdls = test.grpc_handles().await.unwrap();
+    hdls[0]
+        .bdev
+        .create(BdevUri {
+            uri: "malloc:///disk0?size_mb=64".into(),
+        })
+        .await
+        .unwrap();
+    hdls[0]
+        .bdev
+        .share(BdevShareRequest {
+            name: "disk0".into(),
+            proto: "nvmf".into(),
+        })
+        .await
+        .unwrap();
+    let child_uri = format!(
+        "nvmf://{}:8420/nqn.2019-05.io.openebs:disk0",
+        hdls[0].endpoint.ip()
+    );
+
+    let mayastor = MayastorTest::new(MayastorCliArgs::default());
+    let c = child_uri.clone();
+    mayastor
+        .spawn(async move {
+            nexus_create(
+                NXNAME,
+                32 * 1024 * 1024,
+                None,
+                &[LOCAL_CHILD.to_string(), c],
+            )
+            .await
+            .unwrap();
+        })
+        .await;
+
+    // the remote child faults on the first IO while its target is frozen
+    test.pause("ms1").await.unwrap();
+    mayastor
+        .spawn(async {
+            // the write may fail as the child is retired underneath it
+            let _ = bdev_io::write_some(NXNAME, 0, 0xaa).await;
+        })
+        .await;
+    test.thaw("ms1").await.unwrap();
+
+    // and is reconnected rather than left faulted
+    let c = child_uri.clone();
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+    for _ in 0 .. 30 {
+        ticker.tick().await;
+        let c = c.clone();
+        let open = mayastor
+            .spawn(async move {
+                let nexus = nexus_lookup_mut(NXNAME).unwrap();
+                nexus
+                    .children
+                    .iter()
+                    .find(|ch| ch.name == c)
+                    .unwrap()
+                    .state()
+                    == ChildState::Open
+            })
+            .await;
+        if open {
+            break;
+        }
+    }
+
+    mayastor
+        .spawn(async move {
+            let nexus = nexus_lookup_mut(NXNAME).unwrap();
+            let child = nexus
+                .children
+                .iter()
+                .find(|ch| ch.name == child_uri)
+                .unwrap();
+            assert_eq!(child.state(), ChildState::Open);
+
+            let reconnects = nexus.child_reconnects();
+            assert_eq!(reconnects.len(), 1);
+            assert!(reconnects[0].attempts >= 1);
+            assert!(!reconnects[0].reconnecting);
+
+            // and caught up on the regions written while it was away
+            let history = nexus.rebuild_history();
+            assert_eq!(history.last().unwrap().state, "completed");
+
+            nexus.destroy().await.unwrap();
+        })
+        .await;
+}
@@ -0,0 +1,86 @@
+use mayastor::{
+    bdev::nexus::{nexus_create, nexus_lookup_mut},
+    core::MayastorCliArgs,
+    lvs::Lvs,
+    pool::PoolArgs,
+};
+
+pub mod common;
+use common::MayastorTest;
+
+static DISKNAME: &str = "/tmp/child-size-disk.img";
+static POOL_NAME: &str = "child_size_pool";
+static NEXUS_NAME: &str = "child_size_nexus";
+
+static UUID1: &str = "00000000-76b6-4fcf-864d-1027d4038757";
+static UUID2: &str = "00000000-76b6-4fcf-864d-1027d4038758";
+
+static LVOL_SIZE: u64 = 16 * 1024 * 1024;
+
+#[tokio::test]
+async fn nexus_reconcile_child_sizes() {
+    common::delete_file(&[DISKNAME.into()]);
+    common::truncate_file(DISKNAME, 96 * 1024);
+
+    let ms = MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        let pool = Lvs::create_or_import(PoolArgs {
+            name: POOL_NAME.into(),
+            disks: vec![format!("aio://{}", DISKNAME)],
+            uuid: None,
+        })
+        .await
+        .unwrap();
+        let lvol1 = pool
+            .create_lvol(UUID1, LVOL_SIZE, None, true)
+            .await
+            .unwrap();
+        let lvol2 = pool
+            .create_lvol(UUID2, LVOL_SIZE, None, true)
+            .await
+            .unwrap();
+
+        nexus_create(
+            NEXUS_NAME,
+            8 * 1024 * 1024,
+            None,
+            &[
+                format!("loopback:///{}", UUID1),
+                format!("loopback:///{}", UUID2),
+            ],
+        )
+        .await
+        .unwrap();
+
+        // all children hold the nexus, nothing to reconcile
+        let nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+        let report = nexus.reconcile_child_sizes().await;
+        assert!(report.lagging.is_empty());
+        assert!(report.resized.is_empty());
+        assert!(report.unreconciled.is_empty());
+
+        // a replica of the nexus can be grown while in use, which leaves
+        // the other one behind as if its resize had failed
+        lvol2.resize(2 * LVOL_SIZE).await.unwrap();
+        assert!(lvol2.size() >= 2 * LVOL_SIZE);
+        assert!(lvol1.size() < lvol2.size());
+
+        let child1 = format!("loopback:///{}", UUID1);
+        let report = nexus.reconcile_child_sizes().await;
+        assert_eq!(report.lagging, vec![child1.clone()]);
+        assert_eq!(report.resized, vec![child1]);
+        assert!(report.unreconciled.is_empty());
+        assert_eq!(lvol1.size(), lvol2.size());
+
+        // nothing is left to reconcile
+        let report = nexus.reconcile_child_sizes().await;
+        assert!(report.lagging.is_empty());
+        assert!(report.resized.is_empty());
+
+        nexus.destroy().await.unwrap();
+        pool.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME.into()]);
+}
@@ -0,0 +1,34 @@
+use mayastor::{
+    bdev::nexus::{nexus_create, nexus_lookup_mut, Error, NexusStatus},
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+use common::MayastorTest;
+
+static NEXUS_NAME: &str = "ChildrenNexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD: &str = "malloc:///malloc0?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn nexus_create_children() {
+    let ms = MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        // a nexus without children could never serve IO
+        assert!(matches!(
+            nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &[]).await,
+            Err(Error::InvalidArguments { .. })
+        ));
+        assert!(nexus_lookup_mut(NEXUS_NAME).is_none());
+
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &[CHILD.to_string()])
+            .await
+            .unwrap();
+        let nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.children.len(), 1);
+        assert_eq!(nexus.status(), NexusStatus::Online);
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
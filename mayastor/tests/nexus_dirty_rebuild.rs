@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::nexus::{nexus_create, nexus_lookup_mut, Reason},
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+use common::{bdev_io, MayastorTest};
+
+static NEXUS_NAME: &str = "DirtyRebuildNexus";
+static NEXUS_SIZE: u64 = 32 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/dirty-disk1.img";
+static BDEVNAME1: &str = "aio:///tmp/dirty-disk1.img?blk_size=512";
+static DISKNAME2: &str = "/tmp/dirty-disk2.img";
+static BDEVNAME2: &str = "aio:///tmp/dirty-disk2.img?blk_size=512";
+
+// in KiB
+static FILE_SIZE: u64 = 64 * 1024;
+// each in a region of its own
+static DIRTY_OFFSETS: [u64; 2] = [8 * 1024 * 1024, 20 * 1024 * 1024];
+static REGION_SIZE: u64 = 4 * 1024 * 1024;
+
+#[tokio::test]
+async fn nexus_dirty_rebuild() {
+    common::delete_file(&[DISKNAME1.into(), DISKNAME2.into()]);
+    common::truncate_file(DISKNAME1, FILE_SIZE);
+    common::truncate_file(DISKNAME2, FILE_SIZE);
+
+    let ms = MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[BDEVNAME1.to_string(), BDEVNAME2.to_string()],
+        )
+        .await
+        .unwrap();
+
+        let mut nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+        nexus.as_mut().offline_child(BDEVNAME2).await.unwrap();
+
+        // only reaches the first child and must be caught up on online
+        for offset in DIRTY_OFFSETS.iter() {
+            bdev_io::write_some(NEXUS_NAME, *offset, 0xaa)
+                .await
+                .unwrap();
+        }
+
+        nexus.as_mut().online_child(BDEVNAME2).await.unwrap();
+    })
+    .await;
+
+    loop {
+        let done = ms
+            .spawn(async {
+                !nexus_lookup_mut(NEXUS_NAME)
+                    .unwrap()
+                    .rebuild_history()
+                    .is_empty()
+            })
+            .await;
+        if done {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    ms.spawn(async {
+        let mut nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+        let history = nexus.rebuild_history();
+        let record = &history[0];
+        assert_eq!(record.state, "completed");
+
+        // only the regions written while offline are copied
+        let dirty_bytes = DIRTY_OFFSETS.len() as u64 * REGION_SIZE;
+        assert_eq!(record.bytes_recovered, dirty_bytes);
+        assert!(record.bytes_recovered < NEXUS_SIZE);
+
+        // the data written while offline is now served by the second child
+        nexus
+            .as_mut()
+            .fault_child(BDEVNAME1, Reason::Rpc)
+            .await
+            .unwrap();
+        for offset in DIRTY_OFFSETS.iter() {
+            bdev_io::read_some(NEXUS_NAME, *offset, 0xaa).await.unwrap();
+        }
+
+        nexus.as_mut().destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME1.into(), DISKNAME2.into()]);
+}
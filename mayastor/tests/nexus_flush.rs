@@ -0,0 +1,182 @@
+use std::time::Duration;
+
+use futures::channel::oneshot;
+use libc::c_void;
+use once_cell::sync::OnceCell;
+use spdk_rs::IoVec;
+
+use mayastor::{
+    bdev::{
+        device_open,
+        nexus::{nexus_create, nexus_lookup_mut, ChildState},
+    },
+    core::{
+        BdevHandle,
+        BlockDevice,
+        BlockDeviceHandle,
+        IoCompletionStatus,
+        MayastorCliArgs,
+    },
+};
+
+pub mod common;
+use common::{
+    bdev_io,
+    error_bdev::{
+        create_error_bdev,
+        inject_error,
+        SPDK_BDEV_IO_TYPE_FLUSH,
+        VBDEV_IO_FAILURE,
+    },
+    MayastorTest,
+};
+
+static NEXUS_NAME: &str = "FlushNexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+
+static DISKNAME: &str = "/tmp/flush-disk.img";
+static ERROR_DEVICE: &str = "flush_error_device";
+static EE_ERROR_DEVICE: &str = "EE_flush_error_device";
+static BAD_CHILD: &str = "bdev:///EE_flush_error_device";
+static GOOD_CHILD: &str = "malloc:///malloc0?blk_size=512&size_mb=12";
+
+static FUA_DISKNAME: &str = "/tmp/fua-disk.img";
+static FUA_ERROR_DEVICE: &str = "fua_error_device";
+static FUA_EE_ERROR_DEVICE: &str = "EE_fua_error_device";
+
+pub fn mayastor() -> &'static MayastorTest<'static> {
+    static MAYASTOR: OnceCell<MayastorTest> = OnceCell::new();
+    MAYASTOR.get_or_init(|| MayastorTest::new(MayastorCliArgs::default()))
+}
+
+/// Sends the completion status of a device IO back to the test
+fn io_done(
+    _device: &dyn BlockDevice,
+    status: IoCompletionStatus,
+    ctx: *mut c_void,
+) {
+    let sender = unsafe {
+        Box::from_raw(ctx as *mut oneshot::Sender<IoCompletionStatus>)
+    };
+    sender.send(status).unwrap();
+}
+
+/// Writes the first block of the device with FUA
+async fn fua_write(handle: &dyn BlockDeviceHandle) -> IoCompletionStatus {
+    let block_len = handle.get_device().block_len();
+    let mut buf = handle.dma_malloc(block_len).unwrap();
+    buf.fill(0xaa);
+    let mut iov = IoVec::default();
+    iov.iov_base = *buf;
+    iov.iov_len = block_len;
+
+    let (s, r) = oneshot::channel::<IoCompletionStatus>();
+    handle
+        .writev_blocks_fua(
+            &mut iov,
+            1,
+            0,
+            1,
+            io_done,
+            Box::into_raw(Box::new(s)).cast(),
+        )
+        .unwrap();
+    r.await.unwrap()
+}
+
+#[tokio::test]
+async fn nexus_flush_all_children() {
+    common::delete_file(&[DISKNAME.into()]);
+    common::truncate_file(DISKNAME, 64 * 1024);
+
+    let ms = mayastor();
+    ms.spawn(async {
+        create_error_bdev(ERROR_DEVICE, DISKNAME);
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[BAD_CHILD.to_string(), GOOD_CHILD.to_string()],
+        )
+        .await
+        .unwrap();
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        // both children support flushes, so initiators must send them
+        assert!(h.get_bdev().has_write_cache());
+
+        bdev_io::write_some(NEXUS_NAME, 0, 0xaa).await.unwrap();
+        h.flush().await.unwrap();
+
+        // the flush reaches every child, a child failing it is taken out
+        inject_error(
+            EE_ERROR_DEVICE,
+            SPDK_BDEV_IO_TYPE_FLUSH,
+            VBDEV_IO_FAILURE,
+            1,
+        );
+        let _ = h.flush().await;
+    })
+    .await;
+
+    let mut retired = false;
+    for _ in 0 .. 50 {
+        retired = ms
+            .spawn(async {
+                nexus_lookup_mut(NEXUS_NAME)
+                    .unwrap()
+                    .children
+                    .iter()
+                    .any(|c| {
+                        c.name == BAD_CHILD && c.state() != ChildState::Open
+                    })
+            })
+            .await;
+        if retired {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(retired, "child which failed the flush was not taken out");
+
+    ms.spawn(async {
+        nexus_lookup_mut(NEXUS_NAME)
+            .unwrap()
+            .destroy()
+            .await
+            .unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME.into()]);
+}
+
+#[tokio::test]
+async fn child_fua_write_flushes() {
+    common::delete_file(&[FUA_DISKNAME.into()]);
+    common::truncate_file(FUA_DISKNAME, 64 * 1024);
+
+    mayastor()
+        .spawn(async {
+            create_error_bdev(FUA_ERROR_DEVICE, FUA_DISKNAME);
+            let handle = device_open(FUA_EE_ERROR_DEVICE, true)
+                .unwrap()
+                .into_handle()
+                .unwrap();
+
+            assert_eq!(fua_write(&*handle).await, IoCompletionStatus::Success);
+
+            // a device without FUA of its own completes the write only
+            // once it has flushed it
+            inject_error(
+                FUA_EE_ERROR_DEVICE,
+                SPDK_BDEV_IO_TYPE_FLUSH,
+                VBDEV_IO_FAILURE,
+                1,
+            );
+            assert_ne!(fua_write(&*handle).await, IoCompletionStatus::Success);
+        })
+        .await;
+
+    common::delete_file(&[FUA_DISKNAME.into()]);
+}
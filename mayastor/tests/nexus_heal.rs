@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use futures::FutureExt;
+use mayastor::{
+    bdev::nexus::{
+        nexus_create,
+        nexus_lookup_mut,
+        set_child_replacer,
+        NexusHealPolicy,
+    },
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+use common::{
+    bdev_io,
+    error_bdev::{
+        create_error_bdev,
+        inject_error,
+        SPDK_BDEV_IO_TYPE_WRITE,
+        VBDEV_IO_FAILURE,
+    },
+    MayastorTest,
+};
+
+static NEXUS_NAME: &str = "HealNexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+
+static DISKNAME: &str = "/tmp/heal-disk.img";
+static ERROR_DEVICE: &str = "heal_error_device";
+static EE_ERROR_DEVICE: &str = "EE_heal_error_device";
+static BAD_CHILD: &str = "bdev:///EE_heal_error_device";
+static GOOD_CHILD: &str = "malloc:///malloc0?blk_size=512&size_mb=12";
+static REPLACEMENT: &str = "malloc:///malloc1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn nexus_heal_replaces_faulted_child() {
+    common::delete_file(&[DISKNAME.into()]);
+    common::truncate_file(DISKNAME, 64 * 1024);
+
+    let ms = MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        set_child_replacer(|_nexus, _child| {
+            async { Some(REPLACEMENT.to_string()) }.boxed_local()
+        });
+        create_error_bdev(ERROR_DEVICE, DISKNAME);
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[BAD_CHILD.to_string(), GOOD_CHILD.to_string()],
+        )
+        .await
+        .unwrap();
+
+        let nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+        nexus.set_heal_policy(NexusHealPolicy {
+            auto_replace: true,
+            cooldown_secs: 300,
+        });
+
+        // the failed write retires the child, which is then replaced
+        inject_error(
+            EE_ERROR_DEVICE,
+            SPDK_BDEV_IO_TYPE_WRITE,
+            VBDEV_IO_FAILURE,
+            1,
+        );
+        let _ = bdev_io::write_some(NEXUS_NAME, 0, 0xaa).await;
+    })
+    .await;
+
+    let mut replaced = false;
+    for _ in 0 .. 50 {
+        replaced = ms
+            .spawn(async {
+                let nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+                nexus.children.iter().any(|c| c.name == REPLACEMENT)
+                    && !nexus.children.iter().any(|c| c.name == BAD_CHILD)
+            })
+            .await;
+        if replaced {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(replaced, "faulted child was not replaced");
+
+    ms.spawn(async {
+        nexus_lookup_mut(NEXUS_NAME)
+            .unwrap()
+            .destroy()
+            .await
+            .unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME.into()]);
+}
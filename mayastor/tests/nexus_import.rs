@@ -0,0 +1,68 @@
+use mayastor::{
+    bdev::nexus::{
+        nexus_create_assume_clean,
+        nexus_lookup_mut,
+        ChildState,
+        NexusStatus,
+    },
+    core::{BdevHandle, MayastorCliArgs},
+    nexus_uri::bdev_create,
+    rebuild::RebuildJob,
+};
+
+pub mod common;
+use common::MayastorTest;
+
+static NEXUS_NAME: &str = "ImportNexus";
+static NEXUS_SIZE: u64 = 32 * 1024 * 1024;
+
+/// writes `fill` to the start of the given bdev
+async fn fill_bdev(name: &str, fill: u8) {
+    let h = BdevHandle::open(name, true, false).unwrap();
+    let mut buf = h.dma_malloc(4096).unwrap();
+    buf.fill(fill);
+    h.write_at(0, &buf).await.unwrap();
+}
+
+/// checks that the start of the given bdev still holds `fill`
+async fn check_bdev(name: &str, fill: u8) {
+    let h = BdevHandle::open(name, false, false).unwrap();
+    let mut buf = h.dma_malloc(4096).unwrap();
+    h.read_at(0, &mut buf).await.unwrap();
+    assert!(buf.as_slice().iter().all(|b| *b == fill));
+}
+
+/// Creating a nexus which assumes its children are clean over children that
+/// already hold data trusts them as they are: the control plane picks the
+/// children to assemble, so no child is rebuilt and none is overwritten.
+#[tokio::test]
+async fn nexus_import() {
+    let ms = MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        bdev_create("malloc:///import0?size_mb=64").await.unwrap();
+        bdev_create("malloc:///import1?size_mb=64").await.unwrap();
+        fill_bdev("import0", 0xaa).await;
+        fill_bdev("import1", 0x55).await;
+
+        nexus_create_assume_clean(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &["bdev:///import0".to_string(), "bdev:///import1".to_string()],
+        )
+        .await
+        .unwrap();
+
+        let nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.status(), NexusStatus::Online);
+        assert!(nexus.children.iter().all(|c| c.state() == ChildState::Open));
+        assert_eq!(RebuildJob::count(), 0);
+        assert!(nexus.rebuild_history().is_empty());
+        nexus.destroy().await.unwrap();
+
+        // the data on both children is left alone
+        check_bdev("import0", 0xaa).await;
+        check_bdev("import1", 0x55).await;
+    })
+    .await;
+}
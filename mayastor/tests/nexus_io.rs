@@ -18,6 +18,7 @@ use rpc::mayastor::{
     CreatePoolRequest,
     CreateReplicaRequest,
     DestroyNexusRequest,
+    JsonRpcRequest,
     Null,
     NvmeAnaState,
     PublishNexusRequest,
@@ -516,6 +517,182 @@ async fn nexus_io_resv_acquire() {
     nvme_disconnect_nqn(&rep_nqn);
 }
 
+#[tokio::test]
+/// Create a nexus with a remote replica on 1 node as its child, and another
+/// nexus with the same replica on a 2nd node. Verify that once the 2nd
+/// nexus preempts the reservation of the 1st, only the key of the 2nd
+/// remains registered and holds the reservation.
+async fn nexus_io_resv_preempt() {
+    std::env::set_var("NEXUS_NVMF_RESV_ENABLE", "1");
+    std::env::set_var("MAYASTOR_NVMF_HOSTID", HOSTID0);
+    let test = Builder::new()
+        .name("nexus_resv_preempt_test")
+        .network("10.1.0.0/16")
+        .add_container_bin(
+            "ms2",
+            composer::Binary::from_dbg("mayastor")
+                .with_env("NEXUS_NVMF_RESV_ENABLE", "1")
+                .with_env("MAYASTOR_NVMF_HOSTID", HOSTID1),
+        )
+        .add_container_bin(
+            "ms1",
+            composer::Binary::from_dbg("mayastor")
+                .with_env("NEXUS_NVMF_RESV_ENABLE", "1")
+                .with_env("MAYASTOR_NVMF_HOSTID", HOSTID1),
+        )
+        .with_clean(true)
+        .build()
+        .await
+        .unwrap();
+
+    let mut hdls = test.grpc_handles().await.unwrap();
+
+    // create a pool on remote node 1
+    hdls[0]
+        .mayastor
+        .create_pool(CreatePoolRequest {
+            name: POOL_NAME.to_string(),
+            disks: vec!["malloc:///disk0?size_mb=64".into()],
+        })
+        .await
+        .unwrap();
+
+    // create replica, shared over nvmf
+    hdls[0]
+        .mayastor
+        .create_replica(CreateReplicaRequest {
+            uuid: UUID.to_string(),
+            pool: POOL_NAME.to_string(),
+            size: 32 * 1024 * 1024,
+            thin: false,
+            share: 1,
+        })
+        .await
+        .unwrap();
+
+    let mayastor = get_ms();
+    let ip0 = hdls[0].endpoint.ip();
+    let resv_key = 0xabcd_ef00_1234_5678;
+    mayastor
+        .spawn(async move {
+            let mut nvme_params = NexusNvmeParams::default();
+            nvme_params.set_resv_key(resv_key);
+            // create nexus on local node with remote replica as child
+            nexus_create_v2(
+                &NXNAME.to_string(),
+                32 * 1024 * 1024,
+                UUID,
+                nvme_params,
+                &[format!("nvmf://{}:8420/{}:{}", ip0, HOSTNQN, UUID)],
+            )
+            .await
+            .unwrap();
+        })
+        .await;
+
+    // create nexus on remote node 2 with replica on node 1 as child
+    let resv_key2 = 0xfeed_f00d_bead_5678;
+    hdls[1]
+        .mayastor
+        .create_nexus_v2(CreateNexusV2Request {
+            name: NXNAME.to_string(),
+            uuid: UUID.to_string(),
+            size: 32 * 1024 * 1024,
+            min_cntl_id: 1,
+            max_cntl_id: 0xffef,
+            resv_key: resv_key2,
+            preempt_key: 0,
+            children: [format!("nvmf://{}:8420/{}:{}", ip0, HOSTNQN, UUID)]
+                .to_vec(),
+        })
+        .await
+        .unwrap();
+
+    // Connect to remote replica to check the registered keys
+    let rep_nqn = format!("{}:{}", HOSTNQN, UUID);
+    nvme_connect(&ip0.to_string(), &rep_nqn, true);
+
+    let rep_dev = get_mayastor_nvme_device();
+
+    let v = get_nvme_resv_report(&rep_dev);
+    assert_eq!(v["regctl"], 2, "should have 2 registered controllers");
+
+    // the 2nd nexus preempts the reservation of the 1st
+    hdls[1]
+        .jsonrpc
+        .json_rpc_call(JsonRpcRequest {
+            method: "nexus_resv_preempt".to_string(),
+            params: serde_json::json!({
+                "name": NXNAME,
+                "key": resv_key2,
+                "preempt_key": resv_key,
+                "resv_type": 5,
+            })
+            .to_string(),
+        })
+        .await
+        .unwrap();
+
+    // Verify that only the 2nd nexus is left and holds the reservation
+    let v2 = get_nvme_resv_report(&rep_dev);
+    assert_eq!(
+        v2["rtype"], 5,
+        "should have write exclusive, all registrants reservation"
+    );
+    assert_eq!(v2["regctl"], 1, "should have 1 registered controller");
+    assert_eq!(
+        v2["regctlext"][0]["rcsts"], 1,
+        "should have reservation status as reserved"
+    );
+    assert_eq!(
+        v2["regctlext"][0]["rkey"], resv_key2,
+        "should have the key of the preempting nexus"
+    );
+    assert_eq!(
+        v2["regctlext"][0]["hostid"].as_str().unwrap(),
+        HOSTID1.to_string().replace("-", ""),
+        "should match host ID of the preempting nexus"
+    );
+
+    // a batch reports a failure per nexus rather than failing as a whole
+    let reply = hdls[1]
+        .jsonrpc
+        .json_rpc_call(JsonRpcRequest {
+            method: "nexus_resv_preempt_batch".to_string(),
+            params: serde_json::json!({
+                "operations": [{
+                    "name": "nexus_unknown",
+                    "key": resv_key2,
+                    "preempt_key": resv_key,
+                    "resv_type": 5,
+                }]
+            })
+            .to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let results: serde_json::Value =
+        serde_json::from_str(&reply.result).unwrap();
+    assert_eq!(results[0]["name"], "nexus_unknown");
+    assert!(results[0]["error"].is_string());
+
+    let v3 = get_nvme_resv_report(&rep_dev);
+    assert_eq!(v3["regctlext"][0]["rkey"], resv_key2);
+
+    mayastor
+        .spawn(async move {
+            nexus_lookup_mut(&NXNAME.to_string())
+                .unwrap()
+                .destroy()
+                .await
+                .unwrap();
+        })
+        .await;
+
+    nvme_disconnect_nqn(&rep_nqn);
+}
+
 #[tokio::test]
 /// Create a nexus with a local and a remote replica.
 /// Verify that write-zeroes does actually write zeroes.
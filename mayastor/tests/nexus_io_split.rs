@@ -0,0 +1,68 @@
+use common::compose::{Builder, MayastorTest};
+use mayastor::{
+    bdev::nexus::{nexus_create, nexus_lookup_mut},
+    core::{BdevHandle, MayastorCliArgs},
+};
+use rpc::mayastor::{BdevShareRequest, BdevUri};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "nexus_io_split";
+
+#[tokio::test]
+async fn nexus_io_split() {
+    let compose = Builder::new()
+        .name("cargo-test")
+        .network("10.1.0.0/16")
+        .add_container("ms1")
+        .with_clean(true)
+        .build()
+        .await
+        .unwrap();
+
+    let mut hdls = compose.grpc_handles().await.unwrap();
+    hdls[0]
+        .bdev
+        .create(BdevUri {
+            uri: "malloc:///disk0?size_mb=64".into(),
+        })
+        .await
+        .unwrap();
+    hdls[0]
+        .bdev
+        .share(BdevShareRequest {
+            name: "disk0".into(),
+            proto: "nvmf".into(),
+        })
+        .await
+        .unwrap();
+    let child = format!(
+        "nvmf://{}:8420/nqn.2019-05.io.openebs:disk0",
+        hdls[0].endpoint.ip()
+    );
+
+    let ms = MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async move {
+        nexus_create(NEXUS_NAME, 32 * 1024 * 1024, None, &[child])
+            .await
+            .unwrap();
+
+        // the remote child limits the transfer size of a single IO
+        let nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+        let max_io_size = nexus.max_io_size().unwrap();
+
+        // an IO larger than the limit is split by the nexus
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(max_io_size * 4).unwrap();
+        buf.fill(0xa5);
+        h.write_at(0, &buf).await.unwrap();
+
+        buf.fill(0);
+        assert_eq!(h.read_at(0, &mut buf).await.unwrap(), max_io_size * 4);
+        assert!(buf.as_slice().iter().all(|b| *b == 0xa5));
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,67 @@
+use mayastor::{
+    bdev::nexus::{nexus_create, nexus_lookup_mut, Error, Reason},
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+use common::{bdev_io, MayastorTest};
+
+static NEXUS_NAME: &str = "ReadPrimaryNexus";
+static NEXUS_SIZE: u64 = 32 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/primary-disk1.img";
+static BDEVNAME1: &str = "aio:///tmp/primary-disk1.img?blk_size=512";
+static DISKNAME2: &str = "/tmp/primary-disk2.img";
+static BDEVNAME2: &str = "aio:///tmp/primary-disk2.img?blk_size=512";
+
+static FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+#[tokio::test]
+async fn nexus_read_primary() {
+    common::delete_file(&[DISKNAME1.into(), DISKNAME2.into()]);
+    common::truncate_file(DISKNAME1, FILE_SIZE);
+    common::truncate_file(DISKNAME2, FILE_SIZE);
+
+    let ms = MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[BDEVNAME1.to_string(), BDEVNAME2.to_string()],
+        )
+        .await
+        .unwrap();
+
+        let mut nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+        assert!(matches!(
+            nexus.promote_read_primary("aio:///missing").await,
+            Err(Error::ChildNotFound { .. })
+        ));
+
+        nexus.promote_read_primary(BDEVNAME2).await.unwrap();
+        assert_eq!(nexus.read_primary().as_deref(), Some(BDEVNAME2));
+        bdev_io::write_some(NEXUS_NAME, 0, 0xaa).await.unwrap();
+        bdev_io::read_some(NEXUS_NAME, 0, 0xaa).await.unwrap();
+
+        // reads fall back to the other children while the primary is gone
+        nexus
+            .as_mut()
+            .fault_child(BDEVNAME2, Reason::Rpc)
+            .await
+            .unwrap();
+        bdev_io::read_some(NEXUS_NAME, 0, 0xaa).await.unwrap();
+        assert!(matches!(
+            nexus.promote_read_primary(BDEVNAME2).await,
+            Err(Error::ReadPrimaryNotOpen { .. })
+        ));
+
+        nexus.demote_read_primary(BDEVNAME2).await.unwrap();
+        assert_eq!(nexus.read_primary(), None);
+
+        nexus.as_mut().destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME1.into(), DISKNAME2.into()]);
+}
@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::nexus::{nexus_create, nexus_lookup_mut, ChildState, Nexus, Reason},
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "RebuildCancelNexus";
+static NEXUS_SIZE: u64 = 60 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///malloc0?blk_size=512&size_mb=64";
+static CHILD_2: &str = "malloc:///malloc1?blk_size=512&size_mb=64";
+static CHILD_3: &str = "malloc:///malloc2?blk_size=512&size_mb=64";
+
+#[tokio::test]
+async fn nexus_rebuild_cancel_all() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &[CHILD_1.to_string()])
+            .await
+            .unwrap();
+
+        let mut nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+        nexus.as_mut().add_child(CHILD_2, true).await.unwrap();
+        nexus.as_mut().add_child(CHILD_3, true).await.unwrap();
+
+        // one running rebuild and one queued behind it
+        Nexus::set_max_rebuilds(1).await;
+        nexus.as_mut().start_rebuild(CHILD_2).await.unwrap();
+        nexus.as_mut().pause_rebuild(CHILD_2).await.unwrap();
+        nexus
+            .as_mut()
+            .start_or_queue_rebuild(CHILD_3)
+            .await
+            .unwrap();
+
+        let rebuilds = Nexus::list_rebuilds();
+        assert_eq!(rebuilds.len(), 1);
+        assert_eq!(rebuilds[0].nexus, NEXUS_NAME);
+        assert_eq!(rebuilds[0].child, CHILD_2);
+
+        let cancelled = Nexus::cancel_all_rebuilds();
+        assert_eq!(cancelled.len(), 1);
+        Nexus::set_max_rebuilds(0).await;
+    })
+    .await;
+
+    // the cancelled rebuild goes away and the queued one never starts
+    let mut done = false;
+    for _ in 0 .. 50 {
+        done = ms.spawn(async { Nexus::list_rebuilds().is_empty() }).await;
+        if done {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(done, "rebuild was not cancelled");
+
+    ms.spawn(async {
+        let mut nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+        for child in &[CHILD_2, CHILD_3] {
+            let state = nexus
+                .children
+                .iter()
+                .find(|c| c.name == *child)
+                .unwrap()
+                .state();
+            assert_eq!(state, ChildState::Faulted(Reason::OutOfSync));
+        }
+
+        // a cancelled rebuild can be started again
+        nexus.as_mut().start_rebuild(CHILD_2).await.unwrap();
+        assert!(nexus.cancel_rebuild(CHILD_3).is_err());
+
+        nexus.as_mut().destroy().await.unwrap();
+    })
+    .await;
+}
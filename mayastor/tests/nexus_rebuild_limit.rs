@@ -0,0 +1,53 @@
+use mayastor::{
+    bdev::nexus::{nexus_create, nexus_lookup_mut, Error, Nexus},
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "RebuildLimitNexus";
+static NEXUS_SIZE: u64 = 60 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///malloc0?blk_size=512&size_mb=64";
+static CHILD_2: &str = "malloc:///malloc1?blk_size=512&size_mb=64";
+static CHILD_3: &str = "malloc:///malloc2?blk_size=512&size_mb=64";
+
+#[tokio::test]
+async fn nexus_rebuild_limit() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &[CHILD_1.to_string()])
+            .await
+            .unwrap();
+
+        let mut nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+        nexus.as_mut().add_child(CHILD_2, true).await.unwrap();
+        nexus.as_mut().add_child(CHILD_3, true).await.unwrap();
+
+        Nexus::set_max_rebuilds(1).await;
+
+        // keep the only rebuild slot taken
+        nexus.as_mut().start_rebuild(CHILD_2).await.unwrap();
+        nexus.as_mut().pause_rebuild(CHILD_2).await.unwrap();
+
+        // explicit requests fail while the limit is reached
+        assert!(matches!(
+            nexus.as_mut().start_rebuild(CHILD_3).await,
+            Err(Error::RebuildLimitReached { .. })
+        ));
+
+        // others wait for a free slot
+        nexus
+            .as_mut()
+            .start_or_queue_rebuild(CHILD_3)
+            .await
+            .unwrap();
+        assert!(nexus.get_rebuild_progress(CHILD_3).is_err());
+
+        // lifting the limit starts the queued rebuild
+        Nexus::set_max_rebuilds(0).await;
+        assert!(nexus.get_rebuild_progress(CHILD_3).is_ok());
+
+        nexus.as_mut().destroy().await.unwrap();
+    })
+    .await;
+}
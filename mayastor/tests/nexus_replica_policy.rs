@@ -0,0 +1,134 @@
+use mayastor::{
+    bdev::nexus::{
+        nexus_create,
+        nexus_create_with_replica_policy,
+        nexus_lookup_mut,
+        NexusReplicaPolicy,
+        NexusStatus,
+        OnInsufficientReplicas,
+        Reason,
+    },
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+use common::bdev_io;
+
+static NEXUS_NAME: &str = "ReplicaPolicyNexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///malloc0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///malloc1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn nexus_replica_policy_read_only() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+
+        let mut nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+
+        // a minimum of zero healthy children is meaningless
+        assert!(nexus
+            .set_replica_policy(NexusReplicaPolicy {
+                min_healthy: 0,
+                on_insufficient: OnInsufficientReplicas::ReadOnly,
+            })
+            .is_err());
+
+        nexus
+            .set_replica_policy(NexusReplicaPolicy {
+                min_healthy: 2,
+                on_insufficient: OnInsufficientReplicas::ReadOnly,
+            })
+            .unwrap();
+
+        bdev_io::write_some(NEXUS_NAME, 0, 0xaa).await.unwrap();
+
+        nexus
+            .as_mut()
+            .fault_child(CHILD_2, Reason::Rpc)
+            .await
+            .unwrap();
+
+        // one healthy child left: reads are served, writes are rejected
+        bdev_io::read_some(NEXUS_NAME, 0, 0xaa).await.unwrap();
+        assert!(bdev_io::write_some(NEXUS_NAME, 0, 0xbb).await.is_err());
+
+        nexus
+            .set_replica_policy(NexusReplicaPolicy {
+                min_healthy: 2,
+                on_insufficient: OnInsufficientReplicas::Fault,
+            })
+            .unwrap();
+
+        assert!(bdev_io::read_some(NEXUS_NAME, 0, 0xaa).await.is_err());
+
+        nexus.as_mut().destroy().await.unwrap();
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn nexus_replica_policy_min_healthy() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        let children = [CHILD_1.to_string(), CHILD_2.to_string()];
+
+        // the minimum cannot exceed the number of children
+        assert!(nexus_create_with_replica_policy(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            NexusReplicaPolicy {
+                min_healthy: 3,
+                on_insufficient: OnInsufficientReplicas::Fault,
+            },
+            &children,
+        )
+        .await
+        .is_err());
+
+        nexus_create_with_replica_policy(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            NexusReplicaPolicy {
+                min_healthy: 2,
+                on_insufficient: OnInsufficientReplicas::Fault,
+            },
+            &children,
+        )
+        .await
+        .unwrap();
+
+        let mut nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.replica_policy().min_healthy, 2);
+        assert_eq!(nexus.status(), NexusStatus::Online);
+
+        // below the minimum the nexus faults instead of running degraded
+        nexus
+            .as_mut()
+            .fault_child(CHILD_2, Reason::Rpc)
+            .await
+            .unwrap();
+        assert_eq!(nexus.status(), NexusStatus::Faulted);
+
+        nexus
+            .set_replica_policy(NexusReplicaPolicy {
+                min_healthy: 1,
+                on_insufficient: OnInsufficientReplicas::Fault,
+            })
+            .unwrap();
+        assert_eq!(nexus.status(), NexusStatus::Degraded);
+
+        nexus.as_mut().destroy().await.unwrap();
+    })
+    .await;
+}
@@ -1,26 +1,35 @@
 use mayastor::{
-    bdev::nexus::{nexus_create, nexus_lookup_mut},
-    core::{
-        mayastor_env_stop,
-        Bdev,
-        MayastorCliArgs,
-        Protocol,
-        Reactor,
-        Share,
+    bdev::nexus::{
+        nexus_create,
+        nexus_lookup_mut,
+        validate_cntlid_range,
+        ChildState,
+        Error,
     },
+    core::{Bdev, MayastorCliArgs, Protocol, Reactor, Share},
+    subsys::{NvmfError, NvmfSubsystem},
+    target::{iscsi, Side},
 };
+use once_cell::sync::OnceCell;
+use rpc::mayastor::ShareProtocolNexus;
 
 pub mod common;
 use common::MayastorTest;
 
+pub fn mayastor() -> &'static MayastorTest<'static> {
+    static MAYASTOR: OnceCell<MayastorTest> = OnceCell::new();
+
+    MAYASTOR.get_or_init(|| {
+        MayastorTest::new(MayastorCliArgs {
+            reactor_mask: "0x3".into(),
+            ..Default::default()
+        })
+    })
+}
+
 #[tokio::test]
 async fn nexus_share_test() {
-    let args = MayastorCliArgs {
-        reactor_mask: "0x3".into(),
-        ..Default::default()
-    };
-
-    MayastorTest::new(args)
+    mayastor()
         .spawn(async {
             // create a nexus and share it via iSCSI
             Reactor::block_on(async {
@@ -89,8 +98,139 @@ async fn nexus_share_test() {
                 assert_eq!(bdev.shared(), Some(Protocol::Off));
                 nexus.destroy().await.unwrap();
             });
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn nexus_reshare_test() {
+    mayastor()
+        .spawn(async {
+            nexus_create(
+                "nexus_reshare",
+                48 * 1024 * 1024,
+                None,
+                &[
+                    "malloc:///malloc2?size_mb=64".into(),
+                    "malloc:///malloc3?size_mb=64".into(),
+                ],
+            )
+            .await
+            .unwrap();
+
+            let mut nexus = nexus_lookup_mut("nexus_reshare").unwrap();
+            let iscsi_uri = nexus
+                .as_mut()
+                .share(ShareProtocolNexus::NexusIscsi, None)
+                .await
+                .unwrap();
+            assert!(iscsi_uri.starts_with("iscsi://"));
+
+            // resharing with the same protocol keeps the target
+            let uri = nexus
+                .as_mut()
+                .reshare(ShareProtocolNexus::NexusIscsi, None)
+                .await
+                .unwrap();
+            assert_eq!(uri, iscsi_uri);
+
+            // move to nvmf, the iSCSI target must be gone
+            let nvmf_uri = nexus
+                .as_mut()
+                .reshare(ShareProtocolNexus::NexusNvmf, None)
+                .await
+                .unwrap();
+            assert!(nvmf_uri.starts_with("nvmf://"));
+            assert_ne!(nvmf_uri, iscsi_uri);
+            assert_eq!(nexus.shared(), Some(Protocol::Nvmf));
+            assert_eq!(nexus.get_share_uri(), Some(nvmf_uri.clone()));
+            assert!(iscsi::get_uri(Side::Nexus, "nexus_reshare").is_none());
+            assert!(NvmfSubsystem::nqn_lookup("nexus_reshare").is_some());
+
+            // and back to iSCSI, the nvmf subsystem must be gone
+            let uri = nexus
+                .as_mut()
+                .reshare(ShareProtocolNexus::NexusIscsi, None)
+                .await
+                .unwrap();
+            assert!(uri.starts_with("iscsi://"));
+            assert_ne!(uri, nvmf_uri);
+            assert_eq!(nexus.shared(), Some(Protocol::Iscsi));
+            assert!(NvmfSubsystem::nqn_lookup("nexus_reshare").is_none());
+
+            // the children were left alone
+            assert!(nexus
+                .children
+                .iter()
+                .all(|c| c.state() == ChildState::Open));
 
-            mayastor_env_stop(0);
+            nexus.as_mut().unshare_nexus().await.unwrap();
+            nexus.destroy().await.unwrap();
         })
         .await;
 }
+
+#[tokio::test]
+async fn nexus_share_num_paths_test() {
+    mayastor()
+        .spawn(async {
+            nexus_create(
+                "nexus_paths",
+                48 * 1024 * 1024,
+                None,
+                &[
+                    "malloc:///malloc4?size_mb=64".into(),
+                    "malloc:///malloc5?size_mb=64".into(),
+                ],
+            )
+            .await
+            .unwrap();
+
+            let mut nexus = nexus_lookup_mut("nexus_paths").unwrap();
+            nexus
+                .as_mut()
+                .share(ShareProtocolNexus::NexusNvmf, None)
+                .await
+                .unwrap();
+
+            let subsystem = NvmfSubsystem::nqn_lookup("nexus_paths").unwrap();
+            assert_eq!(subsystem.uri_endpoints().unwrap().len(), 1);
+
+            // one listener per path
+            subsystem.set_num_paths(2).await.unwrap();
+            let endpoints = subsystem.uri_endpoints().unwrap();
+            assert_eq!(endpoints.len(), 2);
+            assert_ne!(endpoints[0], endpoints[1]);
+
+            // there are no more ports to listen on
+            assert!(matches!(
+                subsystem.set_num_paths(3).await,
+                Err(NvmfError::Paths { .. })
+            ));
+            assert_eq!(subsystem.uri_endpoints().unwrap().len(), 2);
+
+            nexus.as_mut().unshare_nexus().await.unwrap();
+            nexus.destroy().await.unwrap();
+        })
+        .await;
+}
+
+#[test]
+fn nexus_share_cntlid_range() {
+    assert!(validate_cntlid_range("nexus0", 1, 0xffef, 1).is_ok());
+    assert!(validate_cntlid_range("nexus0", 5, 5, 1).is_ok());
+
+    // an inverted range is rejected
+    assert!(matches!(
+        validate_cntlid_range("nexus0", 10, 1, 1),
+        Err(Error::InvalidArguments { .. })
+    ));
+
+    // out of the range allowed by the spec
+    assert!(validate_cntlid_range("nexus0", 0, 10, 1).is_err());
+    assert!(validate_cntlid_range("nexus0", 1, 0xfff0, 1).is_err());
+
+    // one controller ID per path
+    assert!(validate_cntlid_range("nexus0", 5, 5, 2).is_err());
+    assert!(validate_cntlid_range("nexus0", 5, 6, 2).is_ok());
+}
@@ -0,0 +1,50 @@
+use mayastor::{
+    bdev::nexus::{nexus_create, nexus_lookup_mut, Error},
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+use common::{bdev_io, MayastorTest};
+
+static NEXUS_NAME: &str = "ShrinkNexus";
+static NEXUS_SIZE: u64 = 16 * 1024 * 1024;
+static CHILD: &str = "malloc:///malloc0?blk_size=512&size_mb=20";
+
+#[tokio::test]
+async fn nexus_shrink() {
+    let ms = MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &[CHILD.to_string()])
+            .await
+            .unwrap();
+        let mut nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+
+        // the new size must be smaller and block aligned
+        assert!(nexus.as_mut().shrink(NEXUS_SIZE, false).await.is_err());
+        assert!(nexus.as_mut().shrink(1000, false).await.is_err());
+
+        // data beyond the new size prevents the shrink
+        bdev_io::write_some(NEXUS_NAME, 10 * 1024 * 1024, 0xaa)
+            .await
+            .unwrap();
+        assert!(matches!(
+            nexus.as_mut().shrink(8 * 1024 * 1024, false).await,
+            Err(Error::ShrinkTailInUse { offset, .. }) if offset == 10 * 1024 * 1024
+        ));
+        assert_eq!(nexus.size_in_bytes(), NEXUS_SIZE);
+
+        // an unused tail is cut off, the open child keeps its size
+        let oversized =
+            nexus.as_mut().shrink(12 * 1024 * 1024, false).await.unwrap();
+        assert_eq!(nexus.size_in_bytes(), 12 * 1024 * 1024);
+        assert_eq!(oversized.len(), 1);
+        assert_eq!(oversized[0].child, CHILD);
+
+        // forcing the shrink skips the check
+        nexus.as_mut().shrink(8 * 1024 * 1024, true).await.unwrap();
+        assert_eq!(nexus.size_in_bytes(), 8 * 1024 * 1024);
+
+        nexus.as_mut().destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,141 @@
+use mayastor::{
+    bdev::nexus::{
+        nexus_create,
+        nexus_lookup_mut,
+        ChildState,
+        NexusStatus,
+        NexusTrimPolicy,
+    },
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+use common::{bdev_io, MayastorTest};
+
+static NXNAME: &str = "trim_nexus";
+static NXNAME_MIXED: &str = "trim_nexus_mixed";
+static NEXUS_SIZE: u64 = 32 * 1024 * 1024;
+
+static MALLOC0: &str = "malloc:///trim0?size_mb=64";
+static MALLOC1: &str = "malloc:///trim1?size_mb=64";
+static MALLOC2: &str = "malloc:///trim2?size_mb=64";
+// the null bdev does not support unmap
+static NULL0: &str = "null:///trim3?size_mb=64";
+
+static TRIM_OFFSET: u64 = 4 * 1024 * 1024;
+static TRIM_LEN: u64 = 1024;
+
+/// Reads the nexus block at `offset` straight from the child bdev and tells
+/// whether it still holds `fill`, rather than having been trimmed to zeroes.
+async fn child_holds(nexus: &str, child: &str, offset: u64, fill: u8) -> bool {
+    let data_offset = {
+        let nexus = nexus_lookup_mut(nexus).unwrap();
+        nexus.data_ent_offset * nexus.block_len()
+    };
+    let h = BdevHandle::open(child, false, false).unwrap();
+    let mut buf = h.dma_malloc(512).unwrap();
+    h.read_at(data_offset + offset, &mut buf).await.unwrap();
+    let slice = buf.as_slice();
+    if slice.iter().all(|b| *b == fill) {
+        true
+    } else {
+        assert!(slice.iter().all(|b| *b == 0), "neither data nor trimmed");
+        false
+    }
+}
+
+/// Writes `fill` to the nexus, trims it under the given policy and returns
+/// for each child whether it received the trim.
+async fn trim_with(
+    nexus: &str,
+    children: &[&str],
+    policy: NexusTrimPolicy,
+    fill: u8,
+) -> Vec<bool> {
+    nexus_lookup_mut(nexus).unwrap().set_trim_policy(policy);
+    bdev_io::write_some(nexus, TRIM_OFFSET, fill).await.unwrap();
+    bdev_io::unmap_some(nexus, TRIM_OFFSET, TRIM_LEN)
+        .await
+        .unwrap();
+
+    let mut trimmed = Vec::new();
+    for child in children {
+        trimmed.push(!child_holds(nexus, child, TRIM_OFFSET, fill).await);
+    }
+    trimmed
+}
+
+#[tokio::test]
+async fn nexus_trim_policy() {
+    let ms = MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NXNAME,
+            NEXUS_SIZE,
+            None,
+            &[MALLOC0.to_string(), MALLOC1.to_string()],
+        )
+        .await
+        .unwrap();
+        let children = ["trim0", "trim1"];
+
+        // every child is trimmed
+        let trimmed =
+            trim_with(NXNAME, &children, NexusTrimPolicy::PropagateAll, 0xaa)
+                .await;
+        assert_eq!(trimmed, [true, true]);
+
+        // the trim is completed without reaching any child
+        let trimmed =
+            trim_with(NXNAME, &children, NexusTrimPolicy::Drop, 0xbb).await;
+        assert_eq!(trimmed, [false, false]);
+
+        // every child supports unmap, so every child is trimmed
+        let trimmed =
+            trim_with(NXNAME, &children, NexusTrimPolicy::BestEffort, 0xcc)
+                .await;
+        assert_eq!(trimmed, [true, true]);
+
+        nexus_lookup_mut(NXNAME).unwrap().destroy().await.unwrap();
+    })
+    .await;
+
+    ms.spawn(async {
+        nexus_create(
+            NXNAME_MIXED,
+            NEXUS_SIZE,
+            None,
+            &[MALLOC2.to_string(), NULL0.to_string()],
+        )
+        .await
+        .unwrap();
+
+        // only the child which supports unmap is trimmed, the other one is
+        // skipped rather than failed
+        let trimmed = trim_with(
+            NXNAME_MIXED,
+            &["trim2"],
+            NexusTrimPolicy::BestEffort,
+            0xdd,
+        )
+        .await;
+        assert_eq!(trimmed, [true]);
+
+        let nexus = nexus_lookup_mut(NXNAME_MIXED).unwrap();
+        assert!(nexus.children.iter().all(|c| c.state() == ChildState::Open));
+        assert_eq!(nexus.status(), NexusStatus::Online);
+
+        // nothing reaches the children when trims are dropped
+        let trimmed =
+            trim_with(NXNAME_MIXED, &["trim2"], NexusTrimPolicy::Drop, 0xee)
+                .await;
+        assert_eq!(trimmed, [false]);
+
+        nexus_lookup_mut(NXNAME_MIXED)
+            .unwrap()
+            .destroy()
+            .await
+            .unwrap();
+    })
+    .await;
+}
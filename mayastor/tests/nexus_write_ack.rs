@@ -0,0 +1,154 @@
+use mayastor::{
+    bdev::nexus::{nexus_create, nexus_lookup_mut, ChildState, WriteAckPolicy},
+    core::MayastorCliArgs,
+};
+use once_cell::sync::OnceCell;
+
+pub mod common;
+use common::{
+    bdev_io,
+    error_bdev::{
+        create_error_bdev,
+        inject_error,
+        SPDK_BDEV_IO_TYPE_WRITE,
+        VBDEV_IO_FAILURE,
+    },
+    MayastorTest,
+};
+
+static NEXUS_NAME: &str = "WriteAckNexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+
+static DISKNAME: &str = "/tmp/write-ack-disk.img";
+static ERROR_DEVICE: &str = "write_ack_error_device";
+static EE_ERROR_DEVICE: &str = "EE_write_ack_error_device";
+static BAD_CHILD: &str = "bdev:///EE_write_ack_error_device";
+static GOOD_CHILD1: &str = "malloc:///malloc0?blk_size=512&size_mb=12";
+static GOOD_CHILD2: &str = "malloc:///malloc1?blk_size=512&size_mb=12";
+
+static RETRY_NEXUS_NAME: &str = "WriteAckRetryNexus";
+static RETRY_DISKNAME1: &str = "/tmp/write-ack-retry-disk1.img";
+static RETRY_DISKNAME2: &str = "/tmp/write-ack-retry-disk2.img";
+static RETRY_ERROR_DEVICE1: &str = "write_ack_retry_error_device1";
+static RETRY_ERROR_DEVICE2: &str = "write_ack_retry_error_device2";
+static RETRY_EE_ERROR_DEVICE1: &str = "EE_write_ack_retry_error_device1";
+static RETRY_EE_ERROR_DEVICE2: &str = "EE_write_ack_retry_error_device2";
+static RETRY_BAD_CHILD1: &str = "bdev:///EE_write_ack_retry_error_device1";
+static RETRY_BAD_CHILD2: &str = "bdev:///EE_write_ack_retry_error_device2";
+static RETRY_GOOD_CHILD: &str = "malloc:///malloc2?blk_size=512&size_mb=12";
+
+static MS: OnceCell<MayastorTest> = OnceCell::new();
+
+fn mayastor() -> &'static MayastorTest<'static> {
+    MS.get_or_init(|| MayastorTest::new(MayastorCliArgs::default()))
+}
+
+#[tokio::test]
+async fn nexus_write_ack_on_quorum() {
+    common::delete_file(&[DISKNAME.into()]);
+    common::truncate_file(DISKNAME, 64 * 1024);
+
+    mayastor()
+        .spawn(async {
+            create_error_bdev(ERROR_DEVICE, DISKNAME);
+            nexus_create(
+                NEXUS_NAME,
+                NEXUS_SIZE,
+                None,
+                &[
+                    BAD_CHILD.to_string(),
+                    GOOD_CHILD1.to_string(),
+                    GOOD_CHILD2.to_string(),
+                ],
+            )
+            .await
+            .unwrap();
+
+            let nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+            assert_eq!(nexus.write_ack_policy(), WriteAckPolicy::AckOnAll);
+            nexus.set_write_ack_policy(WriteAckPolicy::AckOnQuorum);
+
+            // the write fails on one of the three children only, so it is
+            // acknowledged and the failed child is taken out
+            inject_error(
+                EE_ERROR_DEVICE,
+                SPDK_BDEV_IO_TYPE_WRITE,
+                VBDEV_IO_FAILURE,
+                1,
+            );
+            bdev_io::write_some(NEXUS_NAME, 0, 0xaa).await.unwrap();
+            bdev_io::read_some(NEXUS_NAME, 0, 0xaa).await.unwrap();
+
+            let nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+            assert!(nexus
+                .children
+                .iter()
+                .all(|c| c.name != BAD_CHILD || c.state() != ChildState::Open));
+
+            nexus.destroy().await.unwrap();
+        })
+        .await;
+
+    common::delete_file(&[DISKNAME.into()]);
+}
+
+#[tokio::test]
+async fn nexus_write_ack_retry() {
+    common::delete_file(&[RETRY_DISKNAME1.into(), RETRY_DISKNAME2.into()]);
+    common::truncate_file(RETRY_DISKNAME1, 64 * 1024);
+    common::truncate_file(RETRY_DISKNAME2, 64 * 1024);
+
+    mayastor()
+        .spawn(async {
+            create_error_bdev(RETRY_ERROR_DEVICE1, RETRY_DISKNAME1);
+            create_error_bdev(RETRY_ERROR_DEVICE2, RETRY_DISKNAME2);
+            nexus_create(
+                RETRY_NEXUS_NAME,
+                NEXUS_SIZE,
+                None,
+                &[
+                    RETRY_BAD_CHILD1.to_string(),
+                    RETRY_BAD_CHILD2.to_string(),
+                    RETRY_GOOD_CHILD.to_string(),
+                ],
+            )
+            .await
+            .unwrap();
+
+            let nexus = nexus_lookup_mut(RETRY_NEXUS_NAME).unwrap();
+            nexus.set_write_ack_policy(WriteAckPolicy::AckOnQuorum);
+
+            // the write fails on two of the three children, so it is retried on
+            // the remaining child and the retry is counted afresh
+            inject_error(
+                RETRY_EE_ERROR_DEVICE1,
+                SPDK_BDEV_IO_TYPE_WRITE,
+                VBDEV_IO_FAILURE,
+                1,
+            );
+            inject_error(
+                RETRY_EE_ERROR_DEVICE2,
+                SPDK_BDEV_IO_TYPE_WRITE,
+                VBDEV_IO_FAILURE,
+                1,
+            );
+            bdev_io::write_some(RETRY_NEXUS_NAME, 0, 0xaa)
+                .await
+                .unwrap();
+            bdev_io::read_some(RETRY_NEXUS_NAME, 0, 0xaa).await.unwrap();
+
+            let nexus = nexus_lookup_mut(RETRY_NEXUS_NAME).unwrap();
+            let open = nexus
+                .children
+                .iter()
+                .filter(|c| c.state() == ChildState::Open)
+                .map(|c| c.name.clone())
+                .collect::<Vec<_>>();
+            assert_eq!(open, vec![RETRY_GOOD_CHILD.to_string()]);
+
+            nexus.destroy().await.unwrap();
+        })
+        .await;
+
+    common::delete_file(&[RETRY_DISKNAME1.into(), RETRY_DISKNAME2.into()]);
+}
@@ -11,6 +11,7 @@ use rpc::mayastor::{
     CreateNexusRequest,
     CreateReply,
     DestroyNexusRequest,
+    JsonRpcRequest,
     Nexus,
     NexusState,
     Null,
@@ -143,6 +144,114 @@ async fn persist_clean_shutdown() {
     assert!(child.healthy);
 }
 
+/// This test checks that a shrunk nexus keeps its size when it is created
+/// again with the size it had before.
+#[tokio::test]
+async fn persist_shrunk_size() {
+    let test = start_infrastructure("persist_shrunk_size").await;
+    let ms1 = &mut test.grpc_handle("ms1").await.unwrap();
+    let ms2 = &mut test.grpc_handle("ms2").await.unwrap();
+
+    let child1 = create_and_share_bdevs(ms2, CHILD1_UUID).await;
+
+    let nexus_uuid = "3d7e1c4a-2f0b-4d36-9a2e-5c8f6b1d0e47";
+    create_nexus(ms1, nexus_uuid, vec![child1.clone()]).await;
+
+    let shrunk_size = 12 * 1024 * 1024;
+    ms1.jsonrpc
+        .json_rpc_call(JsonRpcRequest {
+            method: "nexus_shrink".to_string(),
+            params: format!(
+                "{{\"name\": \"nexus-{}\", \"size\": {}}}",
+                nexus_uuid, shrunk_size
+            ),
+        })
+        .await
+        .expect("Failed to shrink nexus");
+
+    let mut etcd = Client::connect([ETCD_ENDPOINT], None).await.unwrap();
+    let response = etcd.get(nexus_uuid, None).await.expect("No entry found");
+    let value = response.kvs().first().unwrap().value();
+    let nexus_info: NexusInfo = serde_json::from_slice(value).unwrap();
+    assert_eq!(nexus_info.size, Some(shrunk_size));
+
+    ms1.mayastor
+        .destroy_nexus(DestroyNexusRequest {
+            uuid: nexus_uuid.to_string(),
+        })
+        .await
+        .expect("Failed to destroy nexus");
+
+    // created again with its original size
+    create_nexus(ms1, nexus_uuid, vec![child1]).await;
+    let nexus = get_nexus(ms1, nexus_uuid).await.unwrap();
+    assert_eq!(nexus.size, shrunk_size);
+
+    let response = etcd.get(nexus_uuid, None).await.expect("No entry found");
+    let value = response.kvs().first().unwrap().value();
+    let nexus_info: NexusInfo = serde_json::from_slice(value).unwrap();
+    assert_eq!(nexus_info.size, Some(shrunk_size));
+}
+
+/// This test checks that a child which was unhealthy when the nexus was
+/// destroyed is rebuilt when the nexus is created again, unless the nexus is
+/// created assuming that its children are clean.
+#[tokio::test]
+async fn persist_stale_child_rebuild() {
+    let test = start_infrastructure("persist_stale_child_rebuild").await;
+    let ms1 = &mut test.grpc_handle("ms1").await.unwrap();
+    let ms2 = &mut test.grpc_handle("ms2").await.unwrap();
+    let ms3 = &mut test.grpc_handle("ms3").await.unwrap();
+
+    let child1 = create_and_share_bdevs(ms2, CHILD1_UUID).await;
+    let child2 = create_and_share_bdevs(ms3, CHILD2_UUID).await;
+
+    let nexus_uuid = "6c1f0e2b-93a4-4b57-8d1e-2f4a7c9b0d35";
+    let children = vec![child1.clone(), child2.clone()];
+
+    // leaves child2 unhealthy in the persisted info
+    create_nexus(ms1, nexus_uuid, children.clone()).await;
+    remove_child_nexus(ms1, nexus_uuid, &child2).await;
+    destroy_nexus(ms1, nexus_uuid).await;
+
+    // trusted as it is
+    ms1.jsonrpc
+        .json_rpc_call(JsonRpcRequest {
+            method: "nexus_create".to_string(),
+            params: serde_json::json!({
+                "name": format!("nexus-{}", nexus_uuid),
+                "size": 20 * 1024 * 1024,
+                "uuid": nexus_uuid,
+                "children": children,
+                "assume_clean": true,
+            })
+            .to_string(),
+        })
+        .await
+        .expect("Failed to create nexus");
+    assert_eq!(
+        get_nexus_state(ms1, nexus_uuid).await.unwrap(),
+        NexusState::NexusOnline as i32
+    );
+    assert_eq!(
+        get_child(ms1, nexus_uuid, &child2).await.state,
+        ChildState::ChildOnline as i32
+    );
+    remove_child_nexus(ms1, nexus_uuid, &child2).await;
+    destroy_nexus(ms1, nexus_uuid).await;
+
+    // rebuilt
+    create_nexus(ms1, nexus_uuid, children).await;
+    assert_eq!(
+        get_nexus_state(ms1, nexus_uuid).await.unwrap(),
+        NexusState::NexusDegraded as i32
+    );
+    assert_eq!(
+        get_child(ms1, nexus_uuid, &child2).await.state,
+        ChildState::ChildDegraded as i32
+    );
+}
+
 /// This test checks that the state of a child is successfully updated in the
 /// persistent store when there is an I/O failure.
 #[tokio::test]
@@ -402,6 +511,15 @@ async fn add_child_nexus(
         .expect("Failed to add child to nexus.");
 }
 
+async fn destroy_nexus(hdl: &mut RpcHandle, uuid: &str) {
+    hdl.mayastor
+        .destroy_nexus(DestroyNexusRequest {
+            uuid: uuid.to_string(),
+        })
+        .await
+        .expect("Failed to destroy nexus");
+}
+
 async fn remove_child_nexus(hdl: &mut RpcHandle, uuid: &str, child: &str) {
     hdl.mayastor
         .remove_child_nexus(RemoveChildNexusRequest {
@@ -272,7 +272,9 @@ describe('csi', function () {
         // you must change the moac's csi server code as well!
         assert.equal(res.name, 'io.openebs.csi-mayastor');
         assert.equal(res.vendor_version, '0.2');
-        assert.lengthOf(Object.keys(res.manifest), 0);
+        assert.equal(res.manifest.version, '0.2.0');
+        assert.property(res.manifest, 'git_version');
+        assert.property(res.manifest, 'build_date');
         done();
       });
     });
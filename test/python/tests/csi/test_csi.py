@@ -85,6 +85,9 @@ def test_plugin_info(csi_instance):
     info = csi_instance.identity.GetPluginInfo(pb.GetPluginInfoRequest())
     assert info.name == "io.openebs.csi-mayastor"
     assert info.vendor_version == "0.2"
+    assert info.manifest["version"] == "0.2.0"
+    assert "git_version" in info.manifest
+    assert "build_date" in info.manifest
 
 
 def test_plugin_capabilities(csi_instance):